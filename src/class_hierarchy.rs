@@ -0,0 +1,65 @@
+use asr::{string::ArrayCString, Address, Address64};
+
+use crate::memory_source::MemorySource;
+
+/// Offset of the name of an `Il2CppClass`
+pub const CLASS_NAME: u64 = 0x10;
+/// Offset of the parent class of an `Il2CppClass`
+const CLASS_PARENT: u64 = 0x58;
+/// No class in the game is nested this deep, so this protects us from reading garbage forever
+const MAX_DEPTH: usize = 16;
+
+/// Checks whether `class` is, or inherits from, the class called `base_name`
+pub fn derives_from(game: &impl MemorySource, class: Address, base_name: &str) -> bool {
+    let mut class = class;
+
+    for _ in 0..MAX_DEPTH {
+        if class.is_null() {
+            return false;
+        }
+
+        if game
+            .read_pointer_path64::<ArrayCString<128>>(class, &[CLASS_NAME, 0])
+            .is_ok_and(|name| name.matches(base_name))
+        {
+            return true;
+        }
+
+        class = match game.read::<Address64>(class + CLASS_PARENT) {
+            Ok(parent) => parent.into(),
+            Err(_) => return false,
+        };
+    }
+
+    false
+}
+
+/// Remembers which classes inherit from a specific base class, so we only need to walk the class
+/// hierarchy the first time we see a new class.
+#[derive(Default)]
+pub struct InheritanceCache {
+    entries: [(Address, bool); 16],
+    next: usize,
+}
+
+impl InheritanceCache {
+    pub fn derives_from(
+        &mut self,
+        game: &impl MemorySource,
+        class: Address,
+        base_name: &str,
+    ) -> bool {
+        if class.is_null() {
+            return false;
+        }
+
+        if let Some(&(_, result)) = self.entries.iter().find(|(entry, _)| *entry == class) {
+            return result;
+        }
+
+        let result = derives_from(game, class, base_name);
+        self.entries[self.next] = (class, result);
+        self.next = (self.next + 1) % self.entries.len();
+        result
+    }
+}
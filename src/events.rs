@@ -0,0 +1,191 @@
+use asr::{time::Duration, watcher::Watcher, Address};
+
+use crate::{game_mode::GameMode, Watchers};
+
+/// Something that happened in the game during the last tick, as worked out from the changes of
+/// the watchers. The autosplitting logic only has to look for the events it cares about instead
+/// of checking each watcher on its own.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum GameEvent {
+    /// A new act has been loaded, identified by its level ID
+    ActStarted(u32),
+    /// The act timer went back to zero without leaving the act
+    ActRestarted(u32),
+    /// The act timer has started ticking
+    ActTimerStarted,
+    GoalReached,
+    ResultsStarted,
+    ResultsEnded,
+    TimeAttackGoal,
+    TimeAttackExited,
+    /// A boss has been defeated, alongside its class if it's been recognised
+    BossDefeated(Address),
+    LoadStarted,
+    LoadEnded,
+    /// A story has been started
+    StoryStarted(GameMode),
+    SaveSlotChanged(u32),
+    TitleScreenEntered,
+    WorldMapEntered,
+    /// The player is back from a Special Stage, with its emerald
+    SpecialStageCleared,
+    /// A new Chaos Emerald has been obtained, with how many of them the player now has
+    EmeraldObtained(u32),
+    BattleStarted,
+    BattleRoundEnded,
+    BattleEnded,
+}
+
+/// Upper bound on the events a single tick can produce, which is one of each kind at most
+const CAPACITY: usize = 24;
+
+/// The events of a single tick
+#[derive(Default)]
+pub struct GameEvents {
+    events: [Option<GameEvent>; CAPACITY],
+    len: usize,
+}
+
+impl GameEvents {
+    /// Turns the changes of the watchers during the last tick into events
+    pub fn from_watchers(watchers: &Watchers) -> Self {
+        let mut events = Self::default();
+        let changed_to =
+            |watcher: &Watcher<bool>, val: bool| watcher.pair.is_some_and(|p| p.changed_to(&val));
+        let level_id = watchers
+            .level_id
+            .pair
+            .map(|val| val.current)
+            .unwrap_or_default();
+
+        if watchers.level_id.pair.is_some_and(|val| val.changed()) {
+            events.push(GameEvent::ActStarted(level_id));
+        }
+
+        if let Some(stage_time) = watchers.stage_time.pair {
+            if stage_time.old == Duration::ZERO && stage_time.current > Duration::ZERO {
+                events.push(GameEvent::ActTimerStarted);
+            }
+
+            // Leaving the act also sends the timer back to zero, so it only counts as a restart if
+            // we're still in the same game scene
+            let same_scene = watchers.is_game_scene.pair.is_some_and(|val| val.current)
+                && watchers
+                    .scene_controller_class
+                    .pair
+                    .is_some_and(|val| !val.changed());
+
+            if same_scene && stage_time.current < stage_time.old {
+                events.push(GameEvent::ActRestarted(level_id));
+            }
+        }
+
+        // The results screen usually follows the goal sequence, but it also counts as reaching
+        // the goal on its own
+        if changed_to(&watchers.goal_sequence, true) || changed_to(&watchers.goal_ring_flag, true) {
+            events.push(GameEvent::GoalReached);
+        }
+
+        if changed_to(&watchers.result_sequence, true) {
+            events.push(GameEvent::ResultsStarted);
+        }
+
+        if changed_to(&watchers.goal_ring_flag, false) {
+            events.push(GameEvent::ResultsEnded);
+        }
+
+        if changed_to(&watchers.time_attack_goal, true) {
+            events.push(GameEvent::TimeAttackGoal);
+        }
+
+        if changed_to(&watchers.is_time_attack, false) {
+            events.push(GameEvent::TimeAttackExited);
+        }
+
+        if changed_to(&watchers.boss_defeated, true) {
+            events.push(GameEvent::BossDefeated(
+                watchers
+                    .boss_class
+                    .pair
+                    .map_or(Address::NULL, |val| val.current),
+            ));
+        }
+
+        if changed_to(&watchers.is_loading, true) {
+            events.push(GameEvent::LoadStarted);
+        }
+
+        if changed_to(&watchers.is_loading, false) {
+            events.push(GameEvent::LoadEnded);
+        }
+
+        if changed_to(&watchers.start_trigger, true) {
+            events.push(GameEvent::StoryStarted(GameMode::Story));
+        }
+
+        if changed_to(&watchers.start_trigger_trip, true) {
+            events.push(GameEvent::StoryStarted(GameMode::TripStory));
+        }
+
+        if watchers
+            .game_mode
+            .pair
+            .is_some_and(|val| val.changed_to(&GameMode::LastStory))
+        {
+            events.push(GameEvent::StoryStarted(GameMode::LastStory));
+        }
+
+        if let Some(current_slot) = watchers.current_slot.pair.filter(|val| val.changed()) {
+            events.push(GameEvent::SaveSlotChanged(current_slot.current));
+        }
+
+        if changed_to(&watchers.is_title_screen, true) {
+            events.push(GameEvent::TitleScreenEntered);
+        }
+
+        if changed_to(&watchers.is_world_map, true) {
+            events.push(GameEvent::WorldMapEntered);
+        }
+
+        if watchers.special_stage_cleared {
+            events.push(GameEvent::SpecialStageCleared);
+            events.push(GameEvent::EmeraldObtained(
+                watchers.emeralds.pair.map_or(0, |val| val.current),
+            ));
+        }
+
+        if changed_to(&watchers.is_battle, true) {
+            events.push(GameEvent::BattleStarted);
+        }
+
+        // A new round number means the previous round has just ended
+        if watchers
+            .battle_round
+            .pair
+            .is_some_and(|val| val.changed() && val.old != 0 && val.current > val.old)
+        {
+            events.push(GameEvent::BattleRoundEnded);
+        }
+
+        if changed_to(&watchers.is_battle_result, true) {
+            events.push(GameEvent::BattleEnded);
+        }
+
+        events
+    }
+
+    fn push(&mut self, event: GameEvent) {
+        if let Some(slot) = self.events.get_mut(self.len) {
+            *slot = Some(event);
+            self.len += 1;
+        }
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = GameEvent> + '_ {
+        self.events[..self.len].iter().flatten().copied()
+    }
+
+    pub fn contains(&self, event: GameEvent) -> bool {
+        self.iter().any(|val| val == event)
+    }
+}
@@ -0,0 +1,50 @@
+/// The way the game is being played. The values `SysGameManager.gameMode` uses are only known for
+/// the stories, so Time Attack and Battle are recognised through their scenes instead.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub enum GameMode {
+    #[default]
+    Story,
+    TripStory,
+    LastStory,
+    TimeAttack,
+    Battle,
+    /// A mode we don't know about, with its raw value
+    Unknown(u32),
+}
+
+impl GameMode {
+    /// Works out the game mode from the raw value of `SysGameManager.gameMode` and from what's
+    /// currently being played
+    pub const fn new(raw: u32, is_time_attack: bool, is_battle: bool) -> Self {
+        if is_time_attack {
+            return Self::TimeAttack;
+        }
+
+        if is_battle {
+            return Self::Battle;
+        }
+
+        match raw {
+            0 => Self::Story,
+            1 => Self::TripStory,
+            2 => Self::LastStory,
+            _ => Self::Unknown(raw),
+        }
+    }
+
+    /// Whether the mode is one of the stories, where acts are split on
+    pub const fn is_story(self) -> bool {
+        matches!(self, Self::Story | Self::TripStory | Self::LastStory)
+    }
+
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::Story => "Story Mode",
+            Self::TripStory => "Trip's Story",
+            Self::LastStory => "Last Story",
+            Self::TimeAttack => "Time Attack",
+            Self::Battle => "Battle",
+            Self::Unknown(_) => "Unknown",
+        }
+    }
+}
@@ -0,0 +1,152 @@
+use asr::{
+    game_engine::unity::il2cpp::{Class, Image, Module},
+    Process,
+};
+
+/// How many ticks to wait between two attempts at resolving the pending fields
+const RETRY_INTERVAL: u32 = 60;
+
+/// A field offset belonging to a class the game only loads at a later point, such as when a
+/// specific enemy spawns for the first time. As we can't wait on it during `Memory::init`, the
+/// offset is looked up in the background and stays unavailable until then.
+pub struct LateField {
+    class_name: &'static str,
+    field_name: &'static str,
+    offset: Option<u64>,
+    fallback: Option<u64>,
+    /// Set once the class has been found without the field, so we stop looking for it
+    missing: bool,
+    /// Whether we've already reported that the field couldn't be found
+    reported: bool,
+}
+
+impl LateField {
+    pub const fn new(class_name: &'static str, field_name: &'static str) -> Self {
+        Self {
+            class_name,
+            field_name,
+            offset: None,
+            fallback: None,
+            missing: false,
+            reported: false,
+        }
+    }
+
+    /// Sets the offset to use while the field hasn't been resolved yet, if we have one
+    pub const fn with_fallback(mut self, fallback: Option<u64>) -> Self {
+        self.fallback = fallback;
+        self
+    }
+
+    /// Builds an already resolved field, for when the memory layout is known in advance
+    #[cfg(test)]
+    pub const fn resolved(offset: u64) -> Self {
+        Self {
+            class_name: "",
+            field_name: "",
+            offset: Some(offset),
+            fallback: None,
+            missing: false,
+            reported: false,
+        }
+    }
+
+    /// Returns the offset of the field, if it has been resolved already or if we have a fallback
+    pub const fn offset(&self) -> Option<u64> {
+        match self.offset {
+            Some(offset) => Some(offset),
+            None => self.fallback,
+        }
+    }
+
+    const fn is_pending(&self) -> bool {
+        self.offset.is_none() && !self.missing
+    }
+
+    fn resolve(&mut self, game: &Process, module: &Module, class: Option<&Class>) {
+        self.offset = class
+            .and_then(|class| class.get_field_offset(game, module, self.field_name))
+            .map(|offset| offset as _);
+
+        if self.offset.is_some() {
+            crate::log(&format_args!(
+                "  => Resolved {}.{}",
+                self.class_name, self.field_name
+            ));
+            return;
+        }
+
+        // The class is in use whenever we look for it, so if it's there without the field, the
+        // field doesn't exist in this build of the game
+        self.missing = class.is_some();
+
+        if !self.reported {
+            self.reported = true;
+            crate::log(&format_args!(
+                "  => Could not find field {}.{}. The splits relying on it are disabled{}.",
+                self.class_name,
+                self.field_name,
+                if self.missing { "" } else { " until then" }
+            ));
+        }
+    }
+}
+
+/// Keeps hold of the IL2CPP module, so the [`LateField`]s can be resolved after `Memory::init`
+pub struct Resolver {
+    module: Module,
+    image: Image,
+    cooldown: u32,
+}
+
+impl Resolver {
+    pub const fn new(module: Module, image: Image) -> Self {
+        Self {
+            module,
+            image,
+            cooldown: 0,
+        }
+    }
+
+    /// Tries to resolve every field that is still pending. Each field comes with whether the game
+    /// is currently using its class, as looking for a class that isn't loaded yet is wasted
+    /// effort. Looking up classes is expensive, so this only actually happens once every
+    /// [`RETRY_INTERVAL`] ticks, and each class is only looked up once for all of its fields.
+    pub fn resolve(&mut self, game: &Process, fields: &mut [(bool, &mut LateField)]) {
+        if !fields
+            .iter()
+            .any(|(in_use, field)| *in_use && field.is_pending())
+        {
+            return;
+        }
+
+        if self.cooldown > 0 {
+            self.cooldown -= 1;
+            return;
+        }
+
+        self.cooldown = RETRY_INTERVAL;
+
+        // One bit for each field whose class has been looked up during this attempt
+        let mut looked_up = 0u64;
+
+        for i in 0..fields.len() {
+            let (in_use, field) = &fields[i];
+            if looked_up & (1 << i) != 0 || !*in_use || !field.is_pending() {
+                continue;
+            }
+
+            let class_name = field.class_name;
+            let class = self.image.get_class(game, &self.module, class_name);
+
+            for (j, (in_use, field)) in fields.iter_mut().enumerate().skip(i) {
+                if *in_use && field.class_name == class_name {
+                    looked_up |= 1 << j;
+                    if field.is_pending() {
+                        field.resolve(game, &self.module, class.as_ref());
+                    }
+                }
+            }
+        }
+    }
+}
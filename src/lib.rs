@@ -1,4 +1,4 @@
-#![no_std]
+#![cfg_attr(not(test), no_std)]
 #![feature(type_alias_impl_trait, const_async_blocks)]
 #![warn(
     clippy::complexity,
@@ -10,9 +10,8 @@
 )]
 
 use asr::{
-    deep_pointer::DeepPointer,
     future::{next_tick, retry},
-    game_engine::unity::il2cpp::{Module, Version},
+    game_engine::unity::il2cpp::Version,
     settings::Gui,
     string::ArrayCString,
     time::Duration,
@@ -21,13 +20,53 @@ use asr::{
     Address, Address64, Process,
 };
 
+mod class_hierarchy;
+mod events;
+mod game_mode;
+mod late_binding;
+mod lookup;
+mod memory_source;
+mod progress;
+mod route;
+mod stages;
+#[cfg(test)]
+mod tests;
+mod timer_control;
+mod trace;
+mod version;
+
+use class_hierarchy::{InheritanceCache, CLASS_NAME};
+use events::{GameEvent, GameEvents};
+use game_mode::GameMode;
+use late_binding::{LateField, Resolver};
+use lookup::Lookup;
+use memory_source::{MemorySource, PointerPath};
+use progress::RunProgress;
+use route::RouteStep;
+use stages::Stage;
+use timer_control::{LiveSplit, TimerControl};
+
+#[cfg(not(test))]
 asr::panic_handler!();
+#[cfg(not(test))]
 asr::async_main!(nightly);
 
 const PROCESS_NAMES: &[&str] = &["SonicSuperstars.exe"];
 
+/// Tick rate used whenever something we time could happen
+const TICK_RATE_ACTIVE: f64 = 120.0;
+/// Tick rate used in the menus while no run is going on
+const TICK_RATE_IDLE: f64 = 20.0;
+
+// Only the WebAssembly build has an entry point, so the tests would see all of it as unused
+#[cfg_attr(test, allow(dead_code))]
 async fn main() {
     let mut settings = Settings::register();
+    let mut watchers = Watchers::default();
+    let mut reattached = false;
+    let mut tick: u64 = 0;
+    let mut is_recording = false;
+    let mut current_tick_rate = TICK_RATE_ACTIVE;
 
     loop {
         // Hook to the target process
@@ -35,65 +74,78 @@ async fn main() {
 
         process
             .until_closes(async {
-                // Once the target has been found and attached to, set up some default watchers
-                let mut watchers = Watchers::default();
+                // Once the target has been found and attached to, set up some default watchers.
+                // If the game got closed or crashed mid-run, we keep the progress of the run, but
+                // we don't autoreset until the player is back into an act, as most of the values
+                // we read will go through their defaults while the game boots up.
+                let timer_state = timer::state();
+                watchers = Watchers {
+                    accumulated_igt: watchers.accumulated_igt,
+                    progress: watchers.progress,
+                    resuming: reattached
+                        && (timer_state == TimerState::Running
+                            || timer_state == TimerState::Paused),
+                    ..Default::default()
+                };
 
                 // Perform memory scanning to look for the addresses we need
-                let memory = Memory::init(&process).await;
+                let mut memory = Memory::init(&process, settings.verbose_logging).await;
+                let mut is_published = false;
+
+                // The watchers start over, so the next tick of the trace has to list every value again
+                is_recording = false;
 
                 loop {
-                    // Splitting logic. Adapted from OG LiveSplit:
-                    // Order of execution
-                    // 1. update() will always be run first. There are no conditions on the execution of this action.
-                    // 2. If the timer is currently either running or paused, then the isLoading, gameTime, and reset actions will be run.
-                    // 3. If reset does not return true, then the split action will be run.
-                    // 4. If the timer is currently not running (and not paused), then the start action will be run.
                     settings.update();
-                    update_loop(&process, &memory, &mut watchers);
-
-                    let timer_state = timer::state();
-                    if timer_state == TimerState::Running || timer_state == TimerState::Paused {
-                        if let Some(is_loading) = is_loading(&watchers, &settings) {
-                            if is_loading {
-                                timer::pause_game_time()
-                            } else {
-                                timer::resume_game_time()
-                            }
-                        }
-
-                        if let Some(game_time) = game_time(&watchers, &settings, &memory) {
-                            timer::set_game_time(game_time)
-                        }
-
-                        if reset(&watchers, &settings) {
-                            timer::reset()
-                        } else if split(&watchers, &settings) {
-                            timer::split()
-                        }
+                    memory.resolve_late_fields(&process, &watchers);
+                    update_loop(&process, &memory, &mut watchers, &settings);
+                    publish_variables(&watchers, !is_published);
+                    is_published = true;
+
+                    if settings.record_trace {
+                        trace::record(tick, &watchers, !is_recording);
                     }
+                    is_recording = settings.record_trace;
+                    tick += 1;
 
-                    if timer::state() == TimerState::NotRunning && start(&watchers, &settings) {
-                        timer::start();
-                        timer::pause_game_time();
-
-                        if let Some(is_loading) = is_loading(&watchers, &settings) {
-                            if is_loading {
-                                timer::pause_game_time()
-                            } else {
-                                timer::resume_game_time()
-                            }
-                        }
+                    let rate = tick_rate(&watchers);
+                    if rate != current_tick_rate {
+                        asr::set_tick_rate(rate);
+                        current_tick_rate = rate;
                     }
 
+                    update_timer(&mut LiveSplit, &mut watchers, &settings);
+
                     next_tick().await;
                 }
             })
             .await;
+
+        // The game has been closed. Game time needs to stay paused until we hook to it again.
+        let timer_state = timer::state();
+        if timer_state == TimerState::Running || timer_state == TimerState::Paused {
+            timer::pause_game_time();
+        }
+
+        reattached = true;
     }
 }
 
 #[derive(Gui)]
 struct Settings {
+    /// => TIMING METHOD
+    timing_method: TimingMethod,
+    /// => SPLIT TIMING: When to split at the end of each act
+    split_timing: SplitTiming,
+    #[default = false]
+    /// => SPLIT TIMING: Split again on acts and bosses that have already been split on in this run
+    repeat_splits: bool,
+    #[default = false]
+    /// => DEBUG: Record every change of the watched values to the log (for regression testing)
+    record_trace: bool,
+    #[default = false]
+    /// => DEBUG: Verbose logging (unexpected game states, and memory lookups when hooking to the game)
+    verbose_logging: bool,
     #[default = true]
     /// => AUTO START: Enable auto start (Story Mode)
     start_story: bool,
@@ -103,6 +155,56 @@ struct Settings {
     #[default = true]
     /// => AUTO START: Enable auto start (Last Story)
     start_last_story: bool,
+    #[default = true]
+    /// => AUTO RESET: Reset when a new Story Mode / Trip's Story save is created
+    reset_new_save: bool,
+    #[default = false]
+    /// => AUTO RESET: Reset when returning to the title or save select screen
+    reset_title_screen: bool,
+    #[default = true]
+    /// => AUTO RESET: Reset when entering Last Story
+    reset_last_story: bool,
+    #[default = false]
+    /// ---------- TIME ATTACK ----------
+    _time_attack: bool,
+    #[default = false]
+    /// Enable Time Attack mode (overrides every other setting)
+    time_attack: bool,
+    #[default = false]
+    /// ---------- INDIVIDUAL LEVELS ----------
+    _il: bool,
+    #[default = false]
+    /// Enable IL mode (start on act start, split on the goal, reset on restart or on exiting to the map)
+    il_mode: bool,
+    #[default = false]
+    /// ---------- BATTLE MODE ----------
+    _battle: bool,
+    #[default = false]
+    /// Enable Battle Mode (start when a battle begins, final split on the overall results)
+    battle_mode: bool,
+    #[default = true]
+    /// Split at the end of each round
+    battle_round: bool,
+    #[default = false]
+    /// ---------- ALL STORIES ----------
+    _all_stories: bool,
+    #[default = false]
+    /// Enable All Stories mode (start on the new Story Mode save, finish on the Black Dragon)
+    all_stories: bool,
+    #[default = true]
+    /// Split when Trip's Story begins
+    all_stories_trip: bool,
+    #[default = true]
+    /// Split when Last Story begins
+    all_stories_last_story: bool,
+    #[default = false]
+    /// ---------- SPLIT ROUTE ----------
+    _route: bool,
+    #[default = false]
+    /// Follow the route: only split on the enabled acts, emeralds and All Stories transitions in the order the game plays them
+    route: bool,
+    /// What to do when a split happens ahead of the route
+    route_skip: RouteSkip,
     #[default = false]
     /// ---------- STORY MODE ----------
     _story: bool,
@@ -271,26 +373,162 @@ struct Settings {
     #[default = true]
     /// Defeat the black dragon
     black_dragon: bool,
+    #[default = false]
+    /// ---------- BOSS KILL TIMING (split on the boss kill instead of the results screen) ----------
+    _boss_kill: bool,
+    #[default = false]
+    /// Bridge Island Zone - Act 2
+    boss_bridge_island_2: bool,
+    #[default = false]
+    /// Speed Jungle Zone - Act 2 / Act 3
+    boss_speed_jungle_2: bool,
+    #[default = false]
+    /// Sky Temple Zone - Act 1
+    boss_sky_temple_1: bool,
+    #[default = false]
+    /// Pinball Carnival Zone - Act 2
+    boss_pinball_carnival_2: bool,
+    #[default = false]
+    /// Lagoon City Zone - Act 2 / Act 3
+    boss_lagoon_city_2: bool,
+    #[default = false]
+    /// Sand Sanctuary Zone - Act 1
+    boss_sand_sanctuary_1: bool,
+    #[default = false]
+    /// Press Factory Zone - Act 2
+    boss_press_factory_2: bool,
+    #[default = false]
+    /// Golden Capital Zone - Act 2 / Act 3
+    boss_golden_capital_2: bool,
+    #[default = false]
+    /// Cyber Station Zone - Act 1
+    boss_cyber_station_1: bool,
+    #[default = false]
+    /// Frozen Base Zone - Act 2 / Act 3
+    boss_frozen_base_2: bool,
+    #[default = false]
+    /// Egg Fortress Zone - Act 1
+    boss_egg_fortress_1: bool,
+    #[default = false]
+    /// ---------- CHAOS EMERALDS ----------
+    _emeralds: bool,
+    #[default = false]
+    /// Split when leaving a Special Stage after obtaining its emerald
+    special_stage_exit: bool,
+    #[default = false]
+    /// Emerald 1 obtained
+    emerald_1: bool,
+    #[default = false]
+    /// Emerald 2 obtained
+    emerald_2: bool,
+    #[default = false]
+    /// Emerald 3 obtained
+    emerald_3: bool,
+    #[default = false]
+    /// Emerald 4 obtained
+    emerald_4: bool,
+    #[default = false]
+    /// Emerald 5 obtained
+    emerald_5: bool,
+    #[default = false]
+    /// Emerald 6 obtained
+    emerald_6: bool,
+    #[default = false]
+    /// Emerald 7 obtained
+    emerald_7: bool,
+}
+
+#[derive(Gui, Copy, Clone, PartialEq)]
+enum TimingMethod {
+    /// Load removed real time
+    #[default]
+    LoadRemoved,
+    /// In-game time (sum of the act timers)
+    InGameTime,
+}
+
+#[derive(Gui, Copy, Clone, PartialEq)]
+enum SplitTiming {
+    /// When touching the goal
+    GoalTouch,
+    /// When the results screen appears
+    ResultsStart,
+    /// When the results screen ends
+    #[default]
+    ResultsEnd,
+    /// When the next act starts loading
+    NextAct,
+}
+
+#[derive(Gui, Copy, Clone, PartialEq)]
+enum RouteSkip {
+    /// Don't split, and keep waiting for the expected split
+    #[default]
+    Ignore,
+    /// Skip the splits in between and continue from there
+    SkipAhead,
 }
 
 #[derive(Default)]
 struct Watchers {
     start_trigger: Watcher<bool>,
     start_trigger_trip: Watcher<bool>,
-    game_mode: Watcher<u32>,
+    game_mode: Watcher<GameMode>,
+    /// Value of `SysGameManager.gameMode` the game mode is worked out from
+    raw_game_mode: Watcher<u32>,
     level_id: Watcher<u32>,
     is_loading: Watcher<bool>,
     goal_ring_flag: Watcher<bool>,
+    goal_sequence: Watcher<bool>,
+    result_sequence: Watcher<bool>,
+    cleared_level: Option<u32>,
     boss_defeated: Watcher<bool>,
+    /// Whether the current `EnemySpecialBase` has been defeated, even if it isn't a zone boss
+    boss_dead: Watcher<bool>,
+    boss_killed_level: Option<u32>,
+    is_title_screen: Watcher<bool>,
+    stage_time: Watcher<Duration>,
+    accumulated_igt: Duration,
+    /// Whether the time of the current act has already been added to `accumulated_igt`
+    act_banked: bool,
+    progress: RunProgress,
+    events: GameEvents,
+    is_time_attack: Watcher<bool>,
+    time_attack_goal: Watcher<bool>,
+    is_world_map: Watcher<bool>,
+    is_battle: Watcher<bool>,
+    is_battle_result: Watcher<bool>,
+    battle_round: Watcher<u32>,
+    battle_placement: Watcher<u32>,
+    is_special_stage: Watcher<bool>,
+    /// How many emeralds the player had when entering the last Special Stage, until it's cleared
+    special_stage_emeralds: Option<u32>,
+    special_stage_cleared: bool,
+    emeralds: Watcher<u32>,
+    current_slot: Watcher<u32>,
+    scene_controller_class: Watcher<Address>,
+    scene_controller_name: ArrayCString<128>,
+    null_instances: Watcher<u8>,
+    game_scene_classes: InheritanceCache,
+    is_game_scene: Watcher<bool>,
+    boss_classes: InheritanceCache,
+    boss_class: Watcher<Address>,
+    boss_name: ArrayCString<128>,
+    /// Set after reattaching mid-run, until the player is back into an act. Neither reset nor split
+    /// act on the values we read in the meantime, as they go through their defaults while the game
+    /// boots up.
+    resuming: bool,
 }
 
 struct Memory {
-    is_loading: DeepPointer<1>,
-    game_mode: DeepPointer<2>,
+    is_loading: PointerPath<1>,
+    game_mode: PointerPath<2>,
     save_data: SysSaveDataStory,
-    current_scene_controller: DeepPointer<2>,
+    current_scene_controller: PointerPath<2>,
     game_scene_controller_offsets: GameSceneControllerOffsets,
     boss_controller_offsets: EnemySpecialBase,
+    battle_offsets: BRMainGameSceneController,
+    resolver: Option<Resolver>,
 }
 
 struct SysSaveDataStory {
@@ -301,134 +539,214 @@ struct SysSaveDataStory {
     current_slot: u64,
     is_normal_first_play: u64,
     is_trip_first_play: u64,
+    emerald_flags: Option<u64>,
 }
 
 struct GameSceneControllerOffsets {
     stage_info: u64,
+    stage_time: u64,
     is_goal_sequence: u64,
     is_result_sequence: u64,
     is_time_attack_mode: u64,
     active_boss_base: u64,
 }
 
+/// Length of the block the flags of the scene controller are read in
+const SCENE_FLAGS_LEN: usize = 8;
+
+#[derive(Default)]
+struct SceneFlags {
+    is_goal_sequence: bool,
+    is_result_sequence: bool,
+    is_time_attack_mode: bool,
+}
+
+impl GameSceneControllerOffsets {
+    /// The flags are bools declared next to each other, so rather than reading them one by one
+    /// we read the whole block at once. Any flag that doesn't fit in it is read on its own.
+    fn read_flags(&self, game: &impl MemorySource, scene_controller: Address) -> SceneFlags {
+        let start = self
+            .is_goal_sequence
+            .min(self.is_result_sequence)
+            .min(self.is_time_attack_mode);
+        let block = game
+            .read::<[u8; SCENE_FLAGS_LEN]>(scene_controller + start)
+            .ok();
+
+        let flag = |offset: u64| match (block, (offset - start) as usize) {
+            (Some(block), i) if i < SCENE_FLAGS_LEN => block[i] != 0,
+            _ => game
+                .read::<u8>(scene_controller + offset)
+                .is_ok_and(|val| val != 0),
+        };
+
+        SceneFlags {
+            is_goal_sequence: flag(self.is_goal_sequence),
+            is_result_sequence: flag(self.is_result_sequence),
+            is_time_attack_mode: flag(self.is_time_attack_mode),
+        }
+    }
+}
+
 struct EnemySpecialBase {
-    base_type: u64, // Becomes 3 when boss dies
+    base_type: LateField, // Becomes 3 when boss dies
+}
+
+/// Offset of `EnemySpecialBase.baseType` the autosplitter has always relied on, used until the
+/// field has been resolved
+const ENEMY_BASE_TYPE_FALLBACK: u64 = 0x130;
+
+struct BRMainGameSceneController {
+    round: LateField,
+    placement: LateField,
 }
 
 impl Memory {
-    async fn init(game: &Process) -> Self {
-        let il2cpp_module = Module::wait_attach(game, Version::V2020).await;
-        let game_assembly = il2cpp_module.wait_get_default_image(game).await;
+    async fn init(game: &Process, verbose: bool) -> Self {
+        let il2cpp_module = Lookup::module(game, Version::V2020).await;
+        version::log_build(game);
+        let game_assembly = Lookup::default_image(game, &il2cpp_module).await;
+        let lookup = Lookup::new(game, &il2cpp_module, &game_assembly, verbose);
 
         // The main class used for monitoring level progression
-        let scene_manager_class = game_assembly
-            .wait_get_class(game, &il2cpp_module, "Scene_Manager")
-            .await;
+        let scene_manager_class = lookup.class("Scene_Manager").await;
 
         // We need to recover the current game mode in order to differentiate between story mode, trip story and final story
         let game_mode = {
-            let sys_game_manager = game_assembly
-                .wait_get_class(game, &il2cpp_module, "SysGameManager")
+            let sys_game_manager = lookup.class("SysGameManager").await;
+            let sys_game_manager_parent = lookup
+                .parent(
+                    &lookup.parent(&sys_game_manager, "SysGameManager").await,
+                    "SysGameManager's parent",
+                )
+                .await;
+            let game_mode = lookup
+                .field(&sys_game_manager, "SysGameManager", "gameMode")
                 .await;
-            let sys_game_manager_parent = sys_game_manager
-                .wait_get_parent(game, &il2cpp_module)
-                .await
-                .wait_get_parent(game, &il2cpp_module)
+            let static_table = lookup
+                .static_table(&sys_game_manager_parent, "SysGameManager's base class")
                 .await;
-            let game_mode = sys_game_manager
-                .wait_get_field_offset(game, &il2cpp_module, "gameMode")
-                .await as _;
-            let static_table = sys_game_manager_parent
-                .wait_get_static_table(game, &il2cpp_module)
+            let instance = lookup
+                .field(
+                    &sys_game_manager_parent,
+                    "SysGameManager's base class",
+                    "s_Instance",
+                )
                 .await;
-            let instance = sys_game_manager_parent
-                .wait_get_field_offset(game, &il2cpp_module, "s_Instance")
-                .await as _;
-            DeepPointer::new_64bit(static_table, &[instance, game_mode])
+            let path = PointerPath::new(static_table, [instance, game_mode]);
+            lookup.log(&format_args!("SysGameManager.gameMode: {path}"));
+            path
         };
 
         // Self-explanatory. In reality this checks a static field inside the scene_manager class that tells us whenever we are in a transision.
         // It's a good loading variable.
         let is_loading = {
-            let scene_manager_static = scene_manager_class
-                .wait_get_static_table(game, &il2cpp_module)
+            let scene_manager_static = lookup
+                .static_table(&scene_manager_class, "Scene_Manager")
                 .await;
-            let scene_manager_is_in_transition = scene_manager_class
-                .wait_get_field_offset(game, &il2cpp_module, "<IsTransitionPlay>k__BackingField")
-                .await as _;
-            DeepPointer::new_64bit(scene_manager_static, &[scene_manager_is_in_transition])
+            let scene_manager_is_in_transition = lookup
+                .field(
+                    &scene_manager_class,
+                    "Scene_Manager",
+                    "<IsTransitionPlay>k__BackingField",
+                )
+                .await;
+            let path = PointerPath::new(scene_manager_static, [scene_manager_is_in_transition]);
+            lookup.log(&format_args!("Scene_Manager.IsTransitionPlay: {path}"));
+            path
         };
 
         // This is a bit of spaghetti code we use to recover the address of the current SceneController.
         // Not that this links to an abstract class, so we need to check which class that inherits from it we are currently in.
         let current_scene_controller = {
-            let scene_manager_parent = scene_manager_class
-                .wait_get_parent(game, &il2cpp_module)
-                .await
-                .wait_get_parent(game, &il2cpp_module)
+            let scene_manager_parent = lookup
+                .parent(
+                    &lookup.parent(&scene_manager_class, "Scene_Manager").await,
+                    "Scene_Manager's parent",
+                )
                 .await;
-            let scene_manager_parent_static = scene_manager_parent
-                .wait_get_static_table(game, &il2cpp_module)
+            let scene_manager_parent_static = lookup
+                .static_table(&scene_manager_parent, "Scene_Manager's base class")
                 .await;
-            let scene_manager_parent_instance = scene_manager_parent
-                .wait_get_field_offset(game, &il2cpp_module, "s_Instance")
-                .await as _;
-            let scene_manager_current_scene_controller = scene_manager_class
-                .wait_get_field_offset(
-                    game,
-                    &il2cpp_module,
+            let scene_manager_parent_instance = lookup
+                .field(
+                    &scene_manager_parent,
+                    "Scene_Manager's base class",
+                    "s_Instance",
+                )
+                .await;
+            let scene_manager_current_scene_controller = lookup
+                .field(
+                    &scene_manager_class,
+                    "Scene_Manager",
                     "<CurrentSceneController>k__BackingField",
                 )
-                .await as _;
+                .await;
 
-            DeepPointer::new_64bit(
+            let path = PointerPath::new(
                 scene_manager_parent_static,
-                &[
+                [
                     scene_manager_parent_instance,
                     scene_manager_current_scene_controller,
                 ],
-            )
+            );
+            lookup.log(&format_args!(
+                "Scene_Manager.CurrentSceneController: {path}"
+            ));
+            path
         };
 
         // Save data. This class contains stuff about story progression and unlocks.
         // It also tells us if we are starting story mode / trip story the first time, making it a
         // perfect variable for triggering the start of a run.
         let save_data = {
-            let sys_save_manager = game_assembly
-                .wait_get_class(game, &il2cpp_module, "SysSaveManager")
+            let sys_save_manager = lookup.class("SysSaveManager").await;
+            let sys_save_manager_parent = lookup
+                .parent(
+                    &lookup.parent(&sys_save_manager, "SysSaveManager").await,
+                    "SysSaveManager's parent",
+                )
+                .await;
+            let sys_save_manager_instance = lookup
+                .static_table(&sys_save_manager_parent, "SysSaveManager's base class")
                 .await;
-            let sys_save_manager_parent = sys_save_manager
-                .wait_get_parent(game, &il2cpp_module)
-                .await
-                .wait_get_parent(game, &il2cpp_module)
+            let instance = lookup
+                .field(
+                    &sys_save_manager_parent,
+                    "SysSaveManager's base class",
+                    "s_Instance",
+                )
+                .await;
+            let save_data = lookup
+                .field(&sys_save_manager, "SysSaveManager", "sysSaveData")
+                .await;
+            let current_slot = lookup
+                .field(
+                    &sys_save_manager,
+                    "SysSaveManager",
+                    "<CurrentSlotNo>k__BackingField",
+                )
+                .await;
+            let save_datas = lookup
+                .field(
+                    &lookup.class("SysSaveData").await,
+                    "SysSaveData",
+                    "SaveDatas",
+                )
                 .await;
-            let sys_save_manager_instance = sys_save_manager_parent
-                .wait_get_static_table(game, &il2cpp_module)
+
+            let sys_save_data_story = lookup.class("SysSaveDataStory").await;
+            let is_normal_first_play = lookup
+                .field(
+                    &sys_save_data_story,
+                    "SysSaveDataStory",
+                    "IsNormalFirstPlay",
+                )
                 .await;
-            let instance = sys_save_manager_parent
-                .wait_get_field_offset(game, &il2cpp_module, "s_Instance")
-                .await as _;
-            let save_data = sys_save_manager
-                .wait_get_field_offset(game, &il2cpp_module, "sysSaveData")
-                .await as _;
-            let current_slot = sys_save_manager
-                .wait_get_field_offset(game, &il2cpp_module, "<CurrentSlotNo>k__BackingField")
-                .await as _;
-            let save_datas = game_assembly
-                .wait_get_class(game, &il2cpp_module, "SysSaveData")
-                .await
-                .wait_get_field_offset(game, &il2cpp_module, "SaveDatas")
-                .await as _;
-
-            let sys_save_data_story = game_assembly
-                .wait_get_class(game, &il2cpp_module, "SysSaveDataStory")
+            let is_trip_first_play = lookup
+                .field(&sys_save_data_story, "SysSaveDataStory", "IsTripFirstPlay")
                 .await;
-            let is_normal_first_play = sys_save_data_story
-                .wait_get_field_offset(game, &il2cpp_module, "IsNormalFirstPlay")
-                .await as _;
-            let is_trip_first_play = sys_save_data_story
-                .wait_get_field_offset(game, &il2cpp_module, "IsTripFirstPlay")
-                .await as _;
+            let emerald_flags = lookup.optional_field(&sys_save_data_story, "ChaosEmeraldFlags");
 
             SysSaveDataStory {
                 static_table: sys_save_manager_instance,
@@ -438,39 +756,64 @@ impl Memory {
                 current_slot,
                 is_normal_first_play,
                 is_trip_first_play,
+                emerald_flags,
             }
         };
 
         // The the SceneController is just an abstract class, we want to delve deeper into this
         // "GameSceneControllerBase" class in order to recover the offsets we need.
         let game_scene_controller_offsets = {
-            let game_scene_controller = game_assembly
-                .wait_get_class(game, &il2cpp_module, "GameSceneControllerBase")
+            let game_scene_controller = lookup.class("GameSceneControllerBase").await;
+
+            let game_scene_controller_stage_info = lookup
+                .field(
+                    &game_scene_controller,
+                    "GameSceneControllerBase",
+                    "stageInfo",
+                )
+                .await;
+            let game_scene_controller_stage_time = lookup
+                .field(
+                    &game_scene_controller,
+                    "GameSceneControllerBase",
+                    "stageTime",
+                )
+                .await;
+            let game_scene_controller_is_goal_sequence = lookup
+                .field(
+                    &game_scene_controller,
+                    "GameSceneControllerBase",
+                    "isGoalSequence",
+                )
+                .await;
+            let game_scene_controller_is_result_sequence = lookup
+                .field(
+                    &game_scene_controller,
+                    "GameSceneControllerBase",
+                    "isResultSequence",
+                )
                 .await;
 
-            let game_scene_controller_stage_info = game_scene_controller
-                .wait_get_field_offset(game, &il2cpp_module, "stageInfo")
-                .await as _;
-            let game_scene_controller_is_goal_sequence = game_scene_controller
-                .wait_get_field_offset(game, &il2cpp_module, "isGoalSequence")
-                .await as _;
-            let game_scene_controller_is_result_sequence = game_scene_controller
-                .wait_get_field_offset(game, &il2cpp_module, "isResultSequence")
-                .await as _;
-
-            let game_scene_controller = game_assembly
-                .wait_get_class(game, &il2cpp_module, "GameSceneController")
+            let game_scene_controller = lookup.class("GameSceneController").await;
+            let game_scene_controller_is_time_attack_mode = lookup
+                .field(
+                    &game_scene_controller,
+                    "GameSceneController",
+                    "isTimeAttackMode",
+                )
                 .await;
-            let game_scene_controller_is_time_attack_mode = game_scene_controller
-                .wait_get_field_offset(game, &il2cpp_module, "isTimeAttackMode")
-                .await as _;
 
-            let active_boss_base = game_scene_controller
-                .wait_get_field_offset(game, &il2cpp_module, "activeBossBase")
-                .await as _;
+            let active_boss_base = lookup
+                .field(
+                    &game_scene_controller,
+                    "GameSceneController",
+                    "activeBossBase",
+                )
+                .await;
 
             GameSceneControllerOffsets {
                 stage_info: game_scene_controller_stage_info,
+                stage_time: game_scene_controller_stage_time,
                 is_goal_sequence: game_scene_controller_is_goal_sequence,
                 is_result_sequence: game_scene_controller_is_result_sequence,
                 is_time_attack_mode: game_scene_controller_is_time_attack_mode,
@@ -478,22 +821,17 @@ impl Memory {
             }
         };
 
-        // This reports whenever a boss dies. Currently defined without looking for its class as it's not loaded in time for the start of a run
-        let boss_final = {
-            let base_type = 0x130;
-
-            /*
-            let class = game_assembly
-                .wait_get_class(game, &il2cpp_module, "EnemySpecialBase")
-                .await;
-
-
-            let base_type = class
-                .wait_get_field_offset(game, &il2cpp_module, "baseType")
-                .await as _;
-            */
+        // This reports whenever a boss dies. The class is not loaded in time for the start of a run,
+        // so its offset gets resolved later on, once the first boss has spawned.
+        let boss_final = EnemySpecialBase {
+            base_type: LateField::new("EnemySpecialBase", "baseType")
+                .with_fallback(Some(ENEMY_BASE_TYPE_FALLBACK)),
+        };
 
-            EnemySpecialBase { base_type }
+        // Battle mode. The class is only used in multiplayer, so it might not be loaded yet either.
+        let battle_offsets = BRMainGameSceneController {
+            round: LateField::new("BRMainGameSceneController", "currentRound"),
+            placement: LateField::new("BRMainGameSceneController", "playerRank"),
         };
 
         asr::print_limited::<24>(&"  => Autosplitter ready!");
@@ -505,38 +843,142 @@ impl Memory {
             current_scene_controller,
             game_scene_controller_offsets,
             boss_controller_offsets: boss_final,
+            battle_offsets,
+            resolver: Some(Resolver::new(il2cpp_module, game_assembly)),
         }
     }
-}
 
-fn update_loop(game: &Process, addresses: &Memory, watchers: &mut Watchers) {
-    const GAME_SCENE_CONTROLLER_TYPES: &[&str] = &[
-        "GameSceneController",
-        "BlackDragonBattleGameSceneController",
-        "BRMainGameSceneController",
-        "BROverallResultSceneController",
-        "EndingGameSceneController",
-        "MiniActGameSceneController",
-        "ShootingGameSceneController",
-        "WorldMapGameSceneController",
-    ];
+    /// Keeps looking for the offsets of the classes the game loads late
+    fn resolve_late_fields(&mut self, game: &Process, watchers: &Watchers) {
+        let is_boss = watchers
+            .boss_class
+            .pair
+            .is_some_and(|val| !val.current.is_null());
+        let is_battle = watchers.is_battle.pair.is_some_and(|val| val.current);
+
+        if let Some(resolver) = &mut self.resolver {
+            resolver.resolve(
+                game,
+                &mut [
+                    (is_boss, &mut self.boss_controller_offsets.base_type),
+                    (is_battle, &mut self.battle_offsets.round),
+                    (is_battle, &mut self.battle_offsets.placement),
+                ],
+            );
+        }
+    }
+}
 
-    const BOSSES_TYPES: &[&str] = &["Bos111", "Bos112"];
+fn update_loop(
+    game: &impl MemorySource,
+    addresses: &Memory,
+    watchers: &mut Watchers,
+    settings: &Settings,
+) {
+    const TITLE_SCENE_CONTROLLER_TYPES: &[&str] =
+        &["TitleSceneController", "SaveSelectSceneController"];
 
     let current_scene_controller: Address = addresses
         .current_scene_controller
-        .deref::<Address64>(game)
+        .read::<Address64>(game)
         .unwrap_or_default()
         .into();
 
-    let current_scene_controller_name = game
-        .read_pointer_path64::<ArrayCString<128>>(current_scene_controller, &[0, 0x10, 0])
-        .unwrap_or_default();
+    let scene_controller_class: Address = game
+        .read::<Address64>(current_scene_controller)
+        .unwrap_or_default()
+        .into();
 
-    // The main GameSceneController (and its inherited class) are the classes we're interested in for autosplitting purposes.
-    let is_game_scene = GAME_SCENE_CONTROLLER_TYPES
-        .iter()
-        .any(|val| current_scene_controller_name.matches(val));
+    // The name can only change along with the class, so we don't need to read it every tick
+    if watchers
+        .scene_controller_class
+        .pair
+        .is_none_or(|val| val.current != scene_controller_class)
+    {
+        watchers.scene_controller_name = game
+            .read_pointer_path64::<ArrayCString<128>>(scene_controller_class, &[CLASS_NAME, 0])
+            .unwrap_or_default();
+    }
+
+    watchers
+        .scene_controller_class
+        .update_infallible(scene_controller_class);
+    let current_scene_controller_name = watchers.scene_controller_name;
+
+    // Every controller inheriting from GameSceneControllerBase is what we're interested in for autosplitting purposes.
+    // Walking the class hierarchy is expensive, so the result is cached for each class.
+    let is_game_scene = watchers.game_scene_classes.derives_from(
+        game,
+        scene_controller_class,
+        "GameSceneControllerBase",
+    );
+    watchers.is_game_scene.update_infallible(is_game_scene);
+
+    if settings.verbose_logging
+        && watchers
+            .scene_controller_class
+            .pair
+            .is_some_and(|val| val.changed())
+        && !current_scene_controller.is_null()
+        && !is_game_scene
+        && !TITLE_SCENE_CONTROLLER_TYPES
+            .iter()
+            .chain(&["SpecialStageGameSceneController"])
+            .any(|val| current_scene_controller_name.matches(val))
+    {
+        log(&format_args!(
+            "  => Unrecognised scene controller: {}",
+            current_scene_controller_name
+                .validate_utf8()
+                .unwrap_or("<invalid name>")
+        ));
+    }
+
+    // Used for resetting the timer whenever the player quits to the title screen or to the save select menu
+    watchers.is_title_screen.update_infallible(
+        TITLE_SCENE_CONTROLLER_TYPES
+            .iter()
+            .any(|val| current_scene_controller_name.matches(val)),
+    );
+
+    watchers
+        .is_world_map
+        .update_infallible(current_scene_controller_name.matches("WorldMapGameSceneController"));
+
+    watchers.is_special_stage.update_infallible(
+        current_scene_controller_name.matches("SpecialStageGameSceneController"),
+    );
+
+    let is_battle = current_scene_controller_name.matches("BRMainGameSceneController");
+    watchers.is_battle.update_infallible(is_battle);
+    watchers
+        .is_battle_result
+        .update_infallible(current_scene_controller_name.matches("BROverallResultSceneController"));
+
+    // Round number and placement are only meaningful while a battle is in progress
+    watchers.battle_round.update_infallible(
+        match (is_battle, addresses.battle_offsets.round.offset()) {
+            (true, Some(offset)) => game
+                .read::<u32>(current_scene_controller + offset)
+                .unwrap_or_default(),
+            _ => match &watchers.battle_round.pair {
+                Some(x) => x.current,
+                _ => 0,
+            },
+        },
+    );
+
+    watchers.battle_placement.update_infallible(
+        match (is_battle, addresses.battle_offsets.placement.offset()) {
+            (true, Some(offset)) => game
+                .read::<u32>(current_scene_controller + offset)
+                .unwrap_or_default(),
+            _ => match &watchers.battle_placement.pair {
+                Some(x) => x.current,
+                _ => 0,
+            },
+        },
+    );
 
     // Save data stuff we read from memory to determine if we're starting a new game
     let sys_save =
@@ -549,8 +991,11 @@ fn update_loop(game: &Process, addresses: &Memory, watchers: &mut Watchers) {
         0
     };
 
-    let save_slot = {
-        if let Ok(sys_save) = sys_save {
+    watchers.current_slot.update_infallible(current_slot);
+
+    // Creating a new game can replace the save data of the slot, so it's looked up every tick
+    let save_slot = sys_save
+        .and_then(|sys_save| {
             game.read_pointer_path64::<Address64>(
                 sys_save,
                 &[
@@ -559,11 +1004,11 @@ fn update_loop(game: &Process, addresses: &Memory, watchers: &mut Watchers) {
                     0x20 + current_slot.wrapping_mul(8) as u64,
                 ],
             )
-            .ok()
-        } else {
-            None
-        }
-    };
+        })
+        .ok()
+        .filter(|save_slot| !save_slot.is_null());
+
+    restart_save_watchers(watchers);
 
     watchers.start_trigger.update_infallible(!{
         if let Some(save_slot) = save_slot {
@@ -583,6 +1028,17 @@ fn update_loop(game: &Process, addresses: &Memory, watchers: &mut Watchers) {
         }
     });
 
+    // Each of the lowest 7 bits of the flags corresponds to a Chaos Emerald, so we just need to
+    // count them. Reading a single byte keeps this working no matter how wide the field is.
+    watchers
+        .emeralds
+        .update_infallible(match (save_slot, addresses.save_data.emerald_flags) {
+            (Some(save_slot), Some(offset)) => {
+                (game.read::<u8>(save_slot + offset).unwrap_or_default() & 0x7F).count_ones()
+            }
+            _ => 0,
+        });
+
     watchers.level_id.update_infallible(if is_game_scene {
         game.read_pointer_path64(
             current_scene_controller,
@@ -598,28 +1054,48 @@ fn update_loop(game: &Process, addresses: &Memory, watchers: &mut Watchers) {
 
     watchers
         .is_loading
-        .update_infallible(addresses.is_loading.deref(game).unwrap_or_default());
+        .update_infallible(addresses.is_loading.read(game).unwrap_or_default());
 
-    watchers.goal_ring_flag.update_infallible(if is_game_scene {
-        let is_time_attack = game.read::<bool>(
-            current_scene_controller + addresses.game_scene_controller_offsets.is_time_attack_mode,
-        );
+    let flags = if is_game_scene {
+        addresses
+            .game_scene_controller_offsets
+            .read_flags(game, current_scene_controller)
+    } else {
+        SceneFlags::default()
+    };
+    let is_time_attack = flags.is_time_attack_mode;
+    let is_goal_sequence = flags.is_goal_sequence;
+    let is_result_sequence = flags.is_result_sequence;
 
-        if is_time_attack.is_ok_and(|val| val) {
-            false
-        } else {
-            game.read(
-                current_scene_controller
-                    + addresses.game_scene_controller_offsets.is_result_sequence,
-            )
-            .is_ok_and(|val| val)
-                || game
-                    .read(
-                        current_scene_controller
-                            + addresses.game_scene_controller_offsets.is_goal_sequence,
-                    )
-                    .is_ok_and(|val| val)
+    watchers.is_time_attack.update_infallible(is_time_attack);
+
+    // In Time Attack we split as soon as the goal is touched, as that's when the in-game clock stops
+    watchers
+        .time_attack_goal
+        .update_infallible(is_time_attack && is_goal_sequence);
+
+    watchers.goal_sequence.update_infallible(if is_game_scene {
+        !is_time_attack && is_goal_sequence
+    } else {
+        match &watchers.goal_sequence.pair {
+            Some(x) => x.current,
+            _ => false,
         }
+    });
+
+    watchers
+        .result_sequence
+        .update_infallible(if is_game_scene {
+            !is_time_attack && is_result_sequence
+        } else {
+            match &watchers.result_sequence.pair {
+                Some(x) => x.current,
+                _ => false,
+            }
+        });
+
+    watchers.goal_ring_flag.update_infallible(if is_game_scene {
+        !is_time_attack && (is_result_sequence || is_goal_sequence)
     } else {
         match &watchers.goal_ring_flag.pair {
             Some(x) => x.current,
@@ -627,168 +1103,780 @@ fn update_loop(game: &Process, addresses: &Memory, watchers: &mut Watchers) {
         }
     });
 
+    // The in-game timer of the current act. It stops once the goal sequence begins, which is
+    // also the moment we add its final value to the running total.
+    watchers.stage_time.update_infallible(if is_game_scene {
+        Duration::seconds_f32(
+            game.read::<f32>(
+                current_scene_controller + addresses.game_scene_controller_offsets.stage_time,
+            )
+            .unwrap_or_default(),
+        )
+    } else {
+        Duration::ZERO
+    });
+
     watchers
-        .game_mode
-        .update_infallible(addresses.game_mode.deref(game).unwrap_or_default());
+        .raw_game_mode
+        .update_infallible(addresses.game_mode.read::<u32>(game).unwrap_or_default());
+    let game_mode = current_game_mode(watchers);
+
+    // Every boss and mid-boss inherits from EnemySpecialBase, so we don't need to know them in
+    // advance
+    let boss_class: Address = game
+        .read_pointer_path64::<Address64>(
+            current_scene_controller,
+            &[addresses.game_scene_controller_offsets.active_boss_base, 0],
+        )
+        .unwrap_or_default()
+        .into();
 
-    watchers.boss_defeated.update_infallible({
-        if game
-            .read_pointer_path64::<ArrayCString<128>>(
-                current_scene_controller,
-                &[
-                    addresses.game_scene_controller_offsets.active_boss_base,
-                    0,
-                    0x10,
-                    0,
-                ],
-            )
-            .is_ok_and(|val| BOSSES_TYPES.iter().any(|v| val.matches(v)))
-        {
-            game.read_pointer_path64::<u8>(
+    let is_boss = watchers
+        .boss_classes
+        .derives_from(game, boss_class, "EnemySpecialBase");
+
+    let boss_class = if is_boss { boss_class } else { Address::NULL };
+    if watchers
+        .boss_class
+        .pair
+        .is_none_or(|val| val.current != boss_class)
+    {
+        watchers.boss_name = game
+            .read_pointer_path64::<ArrayCString<128>>(boss_class, &[CLASS_NAME, 0])
+            .unwrap_or_default();
+    }
+    watchers.boss_class.update_infallible(boss_class);
+
+    // Acts with a boss can also have mid-bosses, which mustn't be mistaken for the boss itself.
+    // The Black Dragon is fought right after the final boss, so it's treated as part of its zone.
+    let level_id = watchers.level_id.pair.map_or(0, |val| val.current);
+    let boss_zone = match game_mode {
+        GameMode::LastStory => 11,
+        _ => level_id / 10000,
+    };
+    let is_zone_boss = is_boss && stages::is_zone_boss(watchers.boss_name.as_bytes(), boss_zone);
+
+    let base_type = addresses.boss_controller_offsets.base_type.offset();
+    let is_boss_dead = match (is_boss, base_type) {
+        (true, Some(base_type)) => game
+            .read_pointer_path64::<u8>(
                 current_scene_controller,
                 &[
                     addresses.game_scene_controller_offsets.active_boss_base,
-                    addresses.boss_controller_offsets.base_type,
+                    base_type,
                 ],
             )
-            .is_ok_and(|val| val == 3)
+            .is_ok_and(|val| val == 3),
+        _ => false,
+    };
+
+    // Bosses that don't follow the naming of the zone bosses are ignored, so they're logged in
+    // case a boss we should split on has been missed
+    if watchers
+        .boss_dead
+        .update_infallible(is_boss_dead)
+        .changed_to(&true)
+        && !is_zone_boss
+        && settings.verbose_logging
+    {
+        log(&format_args!(
+            "  => Ignored the defeat of {}, which isn't a boss of zone {boss_zone}",
+            watchers
+                .boss_name
+                .validate_utf8()
+                .unwrap_or("<invalid name>")
+        ));
+    }
+
+    watchers
+        .boss_defeated
+        .update_infallible(if is_zone_boss && base_type.is_some() {
+            is_boss_dead
         } else {
             match &watchers.boss_defeated.pair {
                 Some(x) => x.current,
                 _ => false,
             }
+        });
+
+    update_state(watchers, settings);
+
+    if settings.verbose_logging {
+        // The singletons the game relies on get destroyed and recreated during some transitions,
+        // but they shouldn't stay null for long.
+        let is_null = |address: Address| {
+            game.read::<Address64>(address)
+                .ok()
+                .is_none_or(|val| val.is_null())
+        };
+
+        let null_instances = [
+            is_null(addresses.game_mode.base + addresses.game_mode.offsets[0]),
+            sys_save.ok().is_none_or(|val| val.is_null()),
+            is_null(
+                addresses.current_scene_controller.base
+                    + addresses.current_scene_controller.offsets[0],
+            ),
+        ];
+        let null_instances = null_instances
+            .iter()
+            .enumerate()
+            .fold(0, |acc, (i, &val)| acc | ((val as u8) << i));
+
+        if watchers
+            .null_instances
+            .update_infallible(null_instances)
+            .changed()
+        {
+            for (i, name) in [
+                "SysGameManager.s_Instance",
+                "SysSaveManager.s_Instance",
+                "Scene_Manager.s_Instance",
+            ]
+            .iter()
+            .enumerate()
+            {
+                if null_instances & (1 << i) != 0 {
+                    log(&format_args!("  => {name} is null"));
+                }
+            }
         }
-    });
+
+        for event in watchers.events.iter() {
+            log(&format_args!("  => Event: {event:?}"));
+
+            if let GameEvent::ActStarted(level_id) = event {
+                if level_id != 0 && Stage::find(level_id).is_none() {
+                    log(&format_args!("  => Unknown level ID: {level_id}"));
+                }
+            }
+        }
+    }
 }
 
-fn start(watchers: &Watchers, settings: &Settings) -> bool {
-    (settings.start_story
-        && watchers
-            .start_trigger
-            .pair
-            .is_some_and(|val| val.changed_to(&true)))
-        || (settings.start_trip
-            && watchers
-                .start_trigger_trip
-                .pair
-                .is_some_and(|val| val.changed_to(&true)))
-        || (settings.start_last_story
+/// Browsing the save select screen swaps the save data we're reading from under us, which mustn't
+/// be mistaken for a new save being created. Starting the watchers of the save over whenever the
+/// slot changes means only changes happening within the same slot count.
+fn restart_save_watchers(watchers: &mut Watchers) {
+    if watchers.current_slot.pair.is_some_and(|val| val.changed()) {
+        watchers.start_trigger = Watcher::default();
+        watchers.start_trigger_trip = Watcher::default();
+        watchers.emeralds = Watcher::default();
+    }
+}
+
+/// Works out the game mode from the watched values
+fn current_game_mode(watchers: &Watchers) -> GameMode {
+    let is = |watcher: &Watcher<bool>| watcher.pair.is_some_and(|val| val.current);
+
+    GameMode::new(
+        watchers.raw_game_mode.pair.map_or(0, |val| val.current),
+        is(&watchers.is_time_attack),
+        is(&watchers.is_battle) || is(&watchers.is_battle_result),
+    )
+}
+
+/// Keeps track of everything that follows from the watched values, and turns their changes into
+/// the events of the tick. Nothing in here reads the game's memory, so replaying a trace goes
+/// through the exact same logic.
+fn update_state(watchers: &mut Watchers, settings: &Settings) {
+    // Emeralds can only be obtained from a Special Stage, which might only get saved once the
+    // player is back from it. Any other change of the count, like loading a different save, is
+    // ignored.
+    let emeralds = watchers.emeralds.pair.map_or(0, |val| val.current);
+    watchers.special_stage_cleared = false;
+    if watchers
+        .is_special_stage
+        .pair
+        .is_some_and(|val| val.changed_to(&true))
+    {
+        watchers.special_stage_emeralds = Some(emeralds);
+    } else if watchers.is_title_screen.pair.is_some_and(|val| val.current) {
+        watchers.special_stage_emeralds = None;
+    } else if let Some(on_entry) = watchers.special_stage_emeralds {
+        if emeralds > on_entry
             && watchers
-                .game_mode
+                .is_special_stage
                 .pair
-                .is_some_and(|val| val.changed_to(&2)))
+                .is_some_and(|val| !val.current)
+        {
+            watchers.special_stage_cleared = true;
+            watchers.special_stage_emeralds = None;
+        }
+    }
+
+    // Remember which act has been cleared, so we can split when the next one loads
+    if let (Some(goal_ring), Some(level_id)) =
+        (&watchers.goal_ring_flag.pair, &watchers.level_id.pair)
+    {
+        if goal_ring.current {
+            watchers.cleared_level = Some(level_id.current);
+        }
+    }
+
+    // The act timer keeps its final value until the next act, or until the act is replayed
+    if watchers.level_id.pair.is_some_and(|val| val.changed())
+        || watchers
+            .stage_time
+            .pair
+            .is_some_and(|val| val.current < val.old)
+    {
+        watchers.act_banked = false;
+    }
+
+    if watchers
+        .goal_ring_flag
+        .pair
+        .is_some_and(|val| val.changed_to(&true))
+    {
+        if let Some(stage_time) = &watchers.stage_time.pair {
+            watchers.accumulated_igt += stage_time.current;
+            watchers.act_banked = true;
+        }
+    }
+
+    let game_mode = current_game_mode(watchers);
+
+    // The raw values of the modes other than the stories aren't known yet, so they're logged
+    // whenever one of them is entered
+    if settings.verbose_logging
+        && !game_mode.is_story()
+        && watchers
+            .game_mode
+            .pair
+            .is_none_or(|val| val.current != game_mode)
+    {
+        log(&format_args!(
+            "  => Game mode: {} (raw value: {})",
+            game_mode.name(),
+            watchers.raw_game_mode.pair.map_or(0, |val| val.current)
+        ));
+    }
+    watchers.game_mode.update_infallible(game_mode);
+
+    let level_id = watchers.level_id.pair.map_or(0, |val| val.current);
+
+    // Remember in which act the boss has been defeated, so the act isn't split again when cleared
+    if watchers
+        .boss_defeated
+        .pair
+        .is_some_and(|val| val.changed_to(&true))
+    {
+        watchers.boss_killed_level = Some(level_id);
+    } else if watchers
+        .level_id
+        .pair
+        .is_some_and(|val| val.changed() && watchers.boss_killed_level == Some(val.current))
+    {
+        watchers.boss_killed_level = None;
+    }
+
+    if watchers.level_id.pair.is_some_and(|val| val.changed()) {
+        watchers.resuming = false;
+    }
+
+    watchers.events = GameEvents::from_watchers(watchers);
+}
+
+/// Runs the timer logic for the current tick, once the watchers have been updated. Adapted from
+/// OG LiveSplit, the order of execution is:
+/// 1. If the timer is currently either running or paused, then the isLoading, gameTime, and reset
+///    actions are run.
+/// 2. If reset does not return true, then the split action is run.
+/// 3. If the timer is currently not running (and not paused), then the start action is run.
+fn update_timer(timer: &mut impl TimerControl, watchers: &mut Watchers, settings: &Settings) {
+    // A timer that isn't running means the previous attempt is over
+    if timer.state() == TimerState::NotRunning {
+        watchers.resuming = false;
+        watchers.progress = RunProgress::default();
+    }
+
+    let timer_state = timer.state();
+    if timer_state == TimerState::Running || timer_state == TimerState::Paused {
+        if let Some(is_loading) = is_loading(watchers, settings) {
+            if is_loading {
+                timer.pause_game_time()
+            } else {
+                timer.resume_game_time()
+            }
+        }
+
+        if let Some(game_time) = game_time(watchers, settings) {
+            timer.set_game_time(game_time)
+        }
+
+        if reset(watchers, settings) {
+            timer.reset()
+        } else if split(watchers, settings) {
+            for _ in 0..watchers.progress.route.take_skips() {
+                timer.skip_split()
+            }
+            timer.split()
+        }
+    }
+
+    if timer.state() == TimerState::NotRunning && start(watchers, settings) {
+        watchers.accumulated_igt = Duration::ZERO;
+        timer.start();
+        timer.pause_game_time();
+
+        if let Some(is_loading) = is_loading(watchers, settings) {
+            if is_loading {
+                timer.pause_game_time()
+            } else {
+                timer.resume_game_time()
+            }
+        }
+    }
+}
+
+/// Slows the autosplitter down while nothing can start a run, which happens to be most of the time
+/// spent in the game's menus. Title and save select screens, the world map and the acts themselves
+/// are all places a run can start from, so those keep the full tick rate.
+fn tick_rate(watchers: &Watchers) -> f64 {
+    let timer_state = timer::state();
+    if timer_state == TimerState::Running || timer_state == TimerState::Paused {
+        return TICK_RATE_ACTIVE;
+    }
+
+    let is = |watcher: &Watcher<bool>| watcher.pair.is_some_and(|val| val.current);
+
+    if is(&watchers.is_title_screen)
+        || is(&watchers.is_world_map)
+        || is(&watchers.is_loading)
+        || is(&watchers.is_battle)
+        || is(&watchers.is_game_scene)
+    {
+        TICK_RATE_ACTIVE
+    } else {
+        TICK_RATE_IDLE
+    }
 }
 
-fn split(watchers: &Watchers, settings: &Settings) -> bool {
-    let Some(game_mode) = &watchers.game_mode.pair else {
+/// Exposes what the autosplitter is seeing as timer variables, so they can be shown in the layout.
+/// Only the ones that changed are sent, unless `full` is set, which is needed on the first tick
+/// after attaching so every variable is shown.
+fn publish_variables(watchers: &Watchers, full: bool) {
+    if full
+        || watchers
+            .scene_controller_class
+            .pair
+            .is_some_and(|val| val.changed())
+    {
+        timer::set_variable(
+            "Scene controller",
+            watchers
+                .scene_controller_name
+                .validate_utf8()
+                .unwrap_or_default(),
+        );
+    }
+
+    let bool_to_str = |val: bool| if val { "Yes" } else { "No" };
+    let current = |watcher: &Watcher<bool>| watcher.pair.is_some_and(|val| val.current);
+
+    if full
+        || watchers.level_id.pair.is_some_and(|val| val.changed())
+        || watchers.game_mode.pair.is_some_and(|val| val.changed())
+    {
+        let level_id = watchers
+            .level_id
+            .pair
+            .map(|val| val.current)
+            .unwrap_or_default();
+        let game_mode = watchers
+            .game_mode
+            .pair
+            .map(|val| val.current)
+            .unwrap_or_default();
+
+        timer::set_variable_int("Level ID", level_id);
+
+        match Stage::find(level_id) {
+            Some(stage) => {
+                timer::set_variable("Zone", stage.zone);
+                timer::set_variable(
+                    "Act",
+                    stage.variant(game_mode).map_or("-", |variant| variant.act),
+                );
+            }
+            None => {
+                timer::set_variable("Zone", "-");
+                timer::set_variable("Act", "-");
+            }
+        }
+
+        match game_mode {
+            GameMode::Unknown(val) => timer::set_variable_int("Game mode", val),
+            game_mode => timer::set_variable("Game mode", game_mode.name()),
+        }
+    }
+
+    if full || watchers.is_loading.pair.is_some_and(|val| val.changed()) {
+        timer::set_variable("Loading", bool_to_str(current(&watchers.is_loading)));
+    }
+
+    if full
+        || watchers
+            .goal_ring_flag
+            .pair
+            .is_some_and(|val| val.changed())
+    {
+        timer::set_variable("Goal", bool_to_str(current(&watchers.goal_ring_flag)));
+    }
+
+    if full || watchers.boss_class.pair.is_some_and(|val| val.changed()) {
+        timer::set_variable(
+            "Boss",
+            match watchers.boss_name.validate_utf8() {
+                Ok(name) if !name.is_empty() => name,
+                _ => "-",
+            },
+        );
+    }
+
+    if full || watchers.boss_defeated.pair.is_some_and(|val| val.changed()) {
+        timer::set_variable(
+            "Boss defeated",
+            bool_to_str(current(&watchers.boss_defeated)),
+        );
+    }
+
+    if full || watchers.current_slot.pair.is_some_and(|val| val.changed()) {
+        timer::set_variable_int(
+            "Save slot",
+            watchers.current_slot.pair.map_or(0, |val| val.current),
+        );
+    }
+
+    if full || watchers.battle_round.pair.is_some_and(|val| val.changed()) {
+        timer::set_variable_int(
+            "Battle round",
+            watchers.battle_round.pair.map_or(0, |val| val.current),
+        );
+    }
+
+    if full
+        || watchers
+            .battle_placement
+            .pair
+            .is_some_and(|val| val.changed())
+    {
+        timer::set_variable_int(
+            "Battle placement",
+            watchers.battle_placement.pair.map_or(0, |val| val.current),
+        );
+    }
+}
+
+fn start(watchers: &Watchers, settings: &Settings) -> bool {
+    let events = &watchers.events;
+
+    // Time Attack runs start as soon as the countdown ends and the in-game clock starts ticking
+    if settings.time_attack {
+        return watchers
+            .game_mode
+            .pair
+            .is_some_and(|val| val.current == GameMode::TimeAttack)
+            && events.contains(GameEvent::ActTimerStarted);
+    }
+
+    if settings.battle_mode {
+        return events.contains(GameEvent::BattleStarted);
+    }
+
+    if settings.il_mode {
+        return events.iter().any(|event| match event {
+            GameEvent::ActStarted(level_id) | GameEvent::ActRestarted(level_id) => {
+                Stage::find(level_id).is_some()
+            }
+            _ => false,
+        });
+    }
+
+    // Trip's Story and Last Story are part of the same run, so only the first story can start it
+    if settings.all_stories {
+        return events.contains(GameEvent::StoryStarted(GameMode::Story));
+    }
+
+    events.iter().any(|event| match event {
+        GameEvent::StoryStarted(GameMode::Story) => settings.start_story,
+        GameEvent::StoryStarted(GameMode::TripStory) => settings.start_trip,
+        GameEvent::StoryStarted(GameMode::LastStory) => settings.start_last_story,
+        _ => false,
+    })
+}
+
+fn split(watchers: &mut Watchers, settings: &Settings) -> bool {
+    if watchers.resuming {
         return false;
-    };
-    let Some(level_id) = &watchers.level_id.pair else {
+    }
+
+    let events = &watchers.events;
+
+    if settings.time_attack {
+        return events.contains(GameEvent::TimeAttackGoal);
+    }
+
+    if settings.battle_mode {
+        return (settings.battle_round && events.contains(GameEvent::BattleRoundEnded))
+            || events.contains(GameEvent::BattleEnded);
+    }
+
+    let Some(game_mode) = watchers.game_mode.pair else {
         return false;
     };
-    let Some(goal_ring) = &watchers.goal_ring_flag.pair else {
+    let Some(level_id) = watchers.level_id.pair else {
         return false;
     };
 
-    // Final boss
-    if level_id.old == 110200
-        && (watchers
-            .boss_defeated
-            .pair
-            .is_some_and(|val| val.changed_to(&true))
-            || goal_ring.changed_to(&true))
+    if settings.il_mode {
+        return events.contains(GameEvent::GoalReached) && Stage::find(level_id.current).is_some();
+    }
+
+    // Following a route, only the next split it expects can happen
+    if settings.route {
+        let route = &mut watchers.progress.route;
+
+        for story in [GameMode::TripStory, GameMode::LastStory] {
+            if events.contains(GameEvent::StoryStarted(story)) {
+                return route.advance(settings, game_mode.current, RouteStep::StoryStarted(story));
+            }
+        }
+
+        if let Some(emerald) = events.iter().find_map(|event| match event {
+            GameEvent::EmeraldObtained(emerald) => Some(emerald),
+            _ => None,
+        }) {
+            return route.advance(settings, game_mode.current, RouteStep::Emerald(emerald));
+        }
+    } else if settings.all_stories {
+        if events.contains(GameEvent::StoryStarted(GameMode::TripStory)) {
+            return settings.all_stories_trip;
+        }
+
+        if events.contains(GameEvent::StoryStarted(GameMode::LastStory)) {
+            return settings.all_stories_last_story;
+        }
+    }
+
+    // Time Attack and Battle have settings of their own, and there's nothing we know to split on in
+    // the other modes
+    if !game_mode.current.is_story() {
+        return false;
+    }
+
+    if !settings.route
+        && settings.special_stage_exit
+        && events.contains(GameEvent::SpecialStageCleared)
     {
-        match game_mode.current {
-            0 => return settings.egg_fortress_2,
-            1 => return settings.trip_egg_fortress_2,
-            _ => (),
-        };
+        return true;
     }
 
-    match game_mode.current {
-        0 => {
-            goal_ring.changed_to(&false)
-                && match level_id.old {
-                    10100 => settings.bridge_island_1,
-                    10200 => settings.bridge_island_2,
-                    600102 => settings.bridge_island_fruit,
-                    20100 => settings.speed_jungle_1,
-                    20200 => settings.speed_jungle_sonic,
-                    20300 => settings.speed_jungle_2,
-                    30100 => settings.sky_temple_1,
-                    40100 => settings.pinball_carnival_1,
-                    40200 => settings.pinball_carnival_2,
-                    600401 => settings.pinball_carnival_fruit,
-                    50100 => settings.lagoon_city_1,
-                    50200 => settings.lagoon_city_amy,
-                    50300 => settings.lagoon_city_2,
-                    60100 => settings.sand_sanctuary_1,
-                    70100 => settings.press_factory_1,
-                    70200 => settings.press_factory_2,
-                    600702 => settings.press_factory_fruit,
-                    80100 => settings.golden_capital_1,
-                    80200 => settings.golden_capital_knuckles,
-                    80300 => settings.golden_capital_2,
-                    90100 => settings.cyber_station_1,
-                    100100 => settings.frozen_base_1,
-                    100200 => settings.frozen_base_tails,
-                    100300 => settings.frozen_base_2,
-                    110100 => settings.egg_fortress_1,
-                    110200 => settings.egg_fortress_2,
-                    _ => false,
-                }
+    if !settings.route
+        && events.iter().any(|event| match event {
+            GameEvent::EmeraldObtained(1) => settings.emerald_1,
+            GameEvent::EmeraldObtained(2) => settings.emerald_2,
+            GameEvent::EmeraldObtained(3) => settings.emerald_3,
+            GameEvent::EmeraldObtained(4) => settings.emerald_4,
+            GameEvent::EmeraldObtained(5) => settings.emerald_5,
+            GameEvent::EmeraldObtained(6) => settings.emerald_6,
+            GameEvent::EmeraldObtained(7) => settings.emerald_7,
+            _ => false,
+        })
+    {
+        return true;
+    }
+
+    let boss_defeated = events
+        .iter()
+        .any(|event| matches!(event, GameEvent::BossDefeated(_)));
+
+    if game_mode.current == GameMode::LastStory {
+        if settings.route {
+            return boss_defeated
+                && watchers.progress.route.advance(
+                    settings,
+                    game_mode.current,
+                    RouteStep::BlackDragon,
+                );
         }
-        1 => {
-            goal_ring.changed_to(&false)
-                && match level_id.old {
-                    10100 => settings.trip_bridge_island_1,
-                    10200 => settings.trip_bridge_island_2,
-                    600102 => settings.trip_bridge_island_fruit,
-                    20100 => settings.trip_speed_jungle_1,
-                    20200 => settings.trip_speed_jungle_2,
-                    20300 => settings.trip_speed_jungle_3,
-                    30100 => settings.trip_sky_temple_1,
-                    40100 => settings.trip_pinball_carnival_1,
-                    40200 => settings.trip_pinball_carnival_2,
-                    600401 => settings.trip_pinball_carnival_fruit,
-                    50100 => settings.trip_lagoon_city_1,
-                    50200 => settings.trip_lagoon_city_2,
-                    50300 => settings.trip_lagoon_city_3,
-                    60100 => settings.trip_sand_sanctuary_1,
-                    70100 => settings.trip_press_factory_1,
-                    70200 => settings.trip_press_factory_2,
-                    600702 => settings.trip_press_factory_fruit,
-                    80100 => settings.trip_golden_capital_1,
-                    80200 => settings.trip_golden_capital_2,
-                    80300 => settings.trip_golden_capital_3,
-                    90100 => settings.trip_cyber_station_1,
-                    100100 => settings.trip_frozen_base_1,
-                    100200 => settings.trip_frozen_base_2,
-                    100300 => settings.trip_frozen_base_3,
-                    110100 => settings.trip_egg_fortress_1,
-                    110200 => settings.trip_egg_fortress_2,
-                    _ => false,
-                }
+
+        return boss_defeated
+            && settings.black_dragon
+            && (watchers.progress.complete_black_dragon() || settings.repeat_splits);
+    }
+
+    // Acts can be timed either on the end of the results screen or, for boss acts, on the boss kill.
+    // The final boss is a special case, as we split as soon as it's defeated. If the boss kill went
+    // unnoticed, the act still splits once it's cleared.
+    let cleared_stage = if level_id.old == 110200
+        && (boss_defeated || events.contains(GameEvent::GoalReached))
+    {
+        Stage::find(level_id.old)
+    } else if boss_defeated {
+        Stage::find(level_id.current).filter(|stage| stage.splits_on_boss_kill(settings))
+    } else {
+        act_cleared(watchers, settings)
+            .and_then(Stage::find)
+            .filter(|stage| {
+                !stage.splits_on_boss_kill(settings) || watchers.boss_killed_level != Some(stage.id)
+            })
+    };
+
+    let Some(stage) = cleared_stage else {
+        return false;
+    };
+
+    let Some(variant) = stage.variant(game_mode.current) else {
+        return false;
+    };
+
+    if !(variant.enabled)(settings) {
+        return false;
+    }
+
+    // Following a route, only the next act it expects can split
+    if settings.route {
+        let step = RouteStep::Act {
+            level_id: stage.id,
+            game_mode: game_mode.current,
+        };
+
+        if !watchers
+            .progress
+            .route
+            .advance(settings, game_mode.current, step)
+        {
+            log(&format_args!(
+                "  => Out of route: {} - {}",
+                stage.zone, variant.act
+            ));
+            return false;
         }
-        2 => {
-            watchers
-                .boss_defeated
+    } else if !watchers.progress.complete_stage(stage, game_mode.current) && !settings.repeat_splits
+    {
+        log(&format_args!(
+            "  => Already split: {} - {}",
+            stage.zone, variant.act
+        ));
+        return false;
+    }
+
+    log(&format_args!(
+        "  => Split: {} - {}",
+        stage.zone, variant.act
+    ));
+    true
+}
+
+fn reset(watchers: &Watchers, settings: &Settings) -> bool {
+    if watchers.resuming {
+        return false;
+    }
+
+    let events = &watchers.events;
+
+    // Retrying or quitting a Time Attack either reloads the stage or sends us back to the menus,
+    // and in both cases the in-game clock goes back to zero
+    if settings.time_attack {
+        return events.contains(GameEvent::TimeAttackExited)
+            || (watchers
+                .game_mode
                 .pair
-                .is_some_and(|val| val.changed_to(&true))
-                && settings.black_dragon
-        }
+                .is_some_and(|val| val.current == GameMode::TimeAttack)
+                && events
+                    .iter()
+                    .any(|event| matches!(event, GameEvent::ActRestarted(_))));
+    }
+
+    // Restarting an act (either manually or after losing a life) sends the act timer back to zero
+    // without changing the level ID
+    if settings.il_mode {
+        return events.iter().any(|event| match event {
+            GameEvent::ActRestarted(level_id) => Stage::find(level_id).is_some(),
+            GameEvent::WorldMapEntered => true,
+            _ => false,
+        });
+    }
+
+    // Moving on to Trip's Story or Last Story is part of the run, so only a new Story Mode save or
+    // going back to the title screen can reset it
+    if settings.all_stories {
+        return events.iter().any(|event| match event {
+            GameEvent::StoryStarted(GameMode::Story) => settings.reset_new_save,
+            GameEvent::TitleScreenEntered => settings.reset_title_screen,
+            _ => false,
+        });
+    }
+
+    events.iter().any(|event| match event {
+        GameEvent::StoryStarted(GameMode::Story | GameMode::TripStory) => settings.reset_new_save,
+        GameEvent::StoryStarted(GameMode::LastStory) => settings.reset_last_story,
+        GameEvent::TitleScreenEntered => settings.reset_title_screen,
         _ => false,
+    })
+}
+
+/// Returns the ID of the act that has just been cleared, according to the chosen split timing
+fn act_cleared(watchers: &Watchers, settings: &Settings) -> Option<u32> {
+    let level_id = watchers.level_id.pair?;
+    let events = &watchers.events;
+
+    let cleared = match settings.split_timing {
+        SplitTiming::GoalTouch => events.contains(GameEvent::GoalReached),
+        SplitTiming::ResultsStart => events.contains(GameEvent::ResultsStarted),
+        SplitTiming::ResultsEnd => events.contains(GameEvent::ResultsEnded),
+        SplitTiming::NextAct => {
+            events.contains(GameEvent::ActStarted(level_id.current))
+                && watchers.cleared_level == Some(level_id.old)
+        }
+    };
+
+    if !cleared {
+        return None;
+    }
+
+    match settings.split_timing {
+        SplitTiming::GoalTouch | SplitTiming::ResultsStart => Some(level_id.current),
+        SplitTiming::ResultsEnd | SplitTiming::NextAct => Some(level_id.old),
     }
 }
 
-fn reset(_watchers: &Watchers, _settings: &Settings) -> bool {
-    false
+/// Prints a message to the LiveSplit log. There's no runtime to print to when testing on the host.
+fn log(message: &dyn core::fmt::Display) {
+    #[cfg(not(test))]
+    asr::print_limited::<128>(message);
+    #[cfg(test)]
+    let _ = message;
 }
 
-fn is_loading(watchers: &Watchers, _settings: &Settings) -> Option<bool> {
-    Some(watchers.is_loading.pair?.current)
+fn is_loading(watchers: &Watchers, settings: &Settings) -> Option<bool> {
+    if settings.time_attack {
+        return Some(true);
+    }
+
+    match settings.timing_method {
+        TimingMethod::LoadRemoved => Some(watchers.is_loading.pair?.current),
+        TimingMethod::InGameTime => Some(true),
+    }
 }
 
-fn game_time(_watchers: &Watchers, _settings: &Settings, _addresses: &Memory) -> Option<Duration> {
-    None
+fn game_time(watchers: &Watchers, settings: &Settings) -> Option<Duration> {
+    if settings.time_attack {
+        return Some(watchers.stage_time.pair?.current);
+    }
+
+    if settings.timing_method != TimingMethod::InGameTime {
+        return None;
+    }
+
+    // Once the goal has been reached, the act time has already been added to the total
+    let current_act = if watchers.act_banked {
+        Duration::ZERO
+    } else {
+        watchers.stage_time.pair?.current
+    };
+
+    Some(watchers.accumulated_igt + current_act)
 }
@@ -0,0 +1,132 @@
+use core::fmt;
+
+use asr::{
+    future::next_tick,
+    game_engine::unity::il2cpp::{Class, Image, Module, Version},
+    Address, Process,
+};
+
+/// How many ticks to wait for a symbol before reporting it as missing (about 10 seconds)
+const TIMEOUT: u32 = 1200;
+
+/// Looks up the IL2CPP symbols we need, waiting for them to become available like the `wait_*`
+/// functions in `asr` do. If a symbol can't be found in a reasonable amount of time, which
+/// usually means a game update renamed or removed it, it gets named in the log instead of
+/// leaving the autosplitter hanging without any explanation.
+pub struct Lookup<'a> {
+    game: &'a Process,
+    module: &'a Module,
+    image: &'a Image,
+    verbose: bool,
+}
+
+impl<'a> Lookup<'a> {
+    pub const fn new(
+        game: &'a Process,
+        module: &'a Module,
+        image: &'a Image,
+        verbose: bool,
+    ) -> Self {
+        Self {
+            game,
+            module,
+            image,
+            verbose,
+        }
+    }
+
+    pub async fn module(game: &Process, version: Version) -> Module {
+        wait_for(&"the IL2CPP module", || Module::attach(game, version)).await
+    }
+
+    pub async fn default_image(game: &Process, module: &Module) -> Image {
+        wait_for(&"the Assembly-CSharp image", || {
+            module.get_default_image(game)
+        })
+        .await
+    }
+
+    pub async fn class(&self, name: &str) -> Class {
+        let class = wait_for(&format_args!("class {name}"), || {
+            self.image.get_class(self.game, self.module, name)
+        })
+        .await;
+
+        self.log(&format_args!("class {name}"));
+        class
+    }
+
+    pub async fn parent(&self, class: &Class, class_name: &str) -> Class {
+        let parent = wait_for(&format_args!("the parent class of {class_name}"), || {
+            class.get_parent(self.game, self.module)
+        })
+        .await;
+
+        self.log(&format_args!("the parent class of {class_name}"));
+        parent
+    }
+
+    pub async fn static_table(&self, class: &Class, class_name: &str) -> Address {
+        let static_table = wait_for(&format_args!("the static table of {class_name}"), || {
+            class.get_static_table(self.game, self.module)
+        })
+        .await;
+
+        self.log(&format_args!(
+            "the static table of {class_name} at {:#X}",
+            static_table.value()
+        ));
+        static_table
+    }
+
+    pub async fn field(&self, class: &Class, class_name: &str, field_name: &str) -> u64 {
+        let offset = wait_for(&format_args!("field {class_name}.{field_name}"), || {
+            class.get_field_offset(self.game, self.module, field_name)
+        })
+        .await as _;
+
+        self.log(&format_args!("{class_name}.{field_name} at {offset:#X}"));
+        offset
+    }
+
+    /// Same as [`Lookup::field`], but for fields that might legitimately be missing. As the
+    /// features relying on them silently stop working, a missing field is always reported.
+    pub fn optional_field(&self, class: &Class, field_name: &str) -> Option<u64> {
+        let offset = class
+            .get_field_offset(self.game, self.module, field_name)
+            .map(|offset| offset as _);
+
+        match offset {
+            Some(offset) => self.log(&format_args!("{field_name} at {offset:#X}")),
+            None => crate::log(&format_args!(
+                "  => Could not find field {field_name}. The splits relying on it are disabled."
+            )),
+        }
+        offset
+    }
+
+    /// Reports a resolved symbol when verbose logging is enabled
+    pub fn log(&self, symbol: &dyn fmt::Display) {
+        if self.verbose {
+            crate::log(&format_args!("  => Found {symbol}"));
+        }
+    }
+}
+
+async fn wait_for<T>(symbol: &dyn fmt::Display, mut f: impl FnMut() -> Option<T>) -> T {
+    let mut ticks = 0;
+    loop {
+        if let Some(val) = f() {
+            return val;
+        }
+
+        ticks += 1;
+        if ticks == TIMEOUT {
+            crate::log(&format_args!(
+                "  => Could not find {symbol}. The game might have been updated."
+            ));
+        }
+
+        next_tick().await;
+    }
+}
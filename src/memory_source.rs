@@ -0,0 +1,63 @@
+use core::fmt;
+
+use asr::{Address, Address64, Process};
+use bytemuck::CheckedBitPattern;
+
+/// Returned whenever a value can't be read from the memory of the game.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Error;
+
+/// Anything we can read the game's memory from. This is implemented by [`Process`] when running
+/// inside LiveSplit, and by an in-memory fake when testing the autosplitting logic on the host.
+pub trait MemorySource {
+    /// Reads a value of type `T` at the specified address.
+    fn read<T: CheckedBitPattern>(&self, address: impl Into<Address>) -> Result<T, Error>;
+
+    /// Follows a path of 64 bit pointers, starting at `address`, and reads a value of type `T`
+    /// at the last offset.
+    fn read_pointer_path64<T: CheckedBitPattern>(
+        &self,
+        address: impl Into<Address>,
+        path: &[u64],
+    ) -> Result<T, Error> {
+        let mut address = address.into();
+        let (&last, path) = path.split_last().ok_or(Error)?;
+        for &offset in path {
+            address = self.read::<Address64>(address + offset)?.into();
+        }
+        self.read(address + last)
+    }
+}
+
+impl MemorySource for Process {
+    fn read<T: CheckedBitPattern>(&self, address: impl Into<Address>) -> Result<T, Error> {
+        Process::read(self, address).map_err(|_| Error)
+    }
+}
+
+/// A pointer path starting from a fixed base address, such as the static table of a class.
+pub struct PointerPath<const N: usize> {
+    pub base: Address,
+    pub offsets: [u64; N],
+}
+
+impl<const N: usize> PointerPath<N> {
+    pub const fn new(base: Address, offsets: [u64; N]) -> Self {
+        Self { base, offsets }
+    }
+
+    /// Dereferences the path and reads a value of type `T` at its end.
+    pub fn read<T: CheckedBitPattern>(&self, game: &impl MemorySource) -> Result<T, Error> {
+        game.read_pointer_path64(self.base, &self.offsets)
+    }
+}
+
+impl<const N: usize> fmt::Display for PointerPath<N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:#X}", self.base.value())?;
+        for offset in self.offsets {
+            write!(f, " -> {offset:#X}")?;
+        }
+        Ok(())
+    }
+}
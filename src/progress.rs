@@ -0,0 +1,46 @@
+use crate::{
+    game_mode::GameMode,
+    route::RouteProgress,
+    stages::{Stage, STAGES},
+};
+
+// Each act gets one bit of the `u64`s below
+const _: () = assert!(
+    STAGES.len() <= u64::BITS as usize,
+    "RunProgress can't keep track of that many acts"
+);
+
+/// Everything that has already been split on during the current attempt, so that replaying an act
+/// or a flickering results screen doesn't split a second time. It's cleared whenever the timer is
+/// reset.
+#[derive(Default, Copy, Clone)]
+pub struct RunProgress {
+    /// One bit for each entry of [`STAGES`], for Story Mode and Trip's Story respectively
+    stages: [u64; 2],
+    black_dragon: bool,
+    pub route: RouteProgress,
+}
+
+impl RunProgress {
+    /// Marks the act as split in the given game mode. Returns `false` if it already was.
+    pub fn complete_stage(&mut self, stage: &Stage, game_mode: GameMode) -> bool {
+        let stages = match game_mode {
+            GameMode::Story => &mut self.stages[0],
+            GameMode::TripStory => &mut self.stages[1],
+            _ => return true,
+        };
+        let Some(index) = STAGES.iter().position(|val| val.id == stage.id) else {
+            return true;
+        };
+
+        let bit = 1u64 << index;
+        let is_new = *stages & bit == 0;
+        *stages |= bit;
+        is_new
+    }
+
+    /// Marks the Black Dragon as defeated. Returns `false` if it already was.
+    pub fn complete_black_dragon(&mut self) -> bool {
+        !core::mem::replace(&mut self.black_dragon, true)
+    }
+}
@@ -0,0 +1,126 @@
+use crate::{game_mode::GameMode, stages::STAGES, RouteSkip, Settings};
+
+/// A split the chosen route expects to happen
+#[derive(Copy, Clone, PartialEq, Eq)]
+pub enum RouteStep {
+    /// Clearing an act, or defeating its boss, in the given game mode
+    Act {
+        level_id: u32,
+        game_mode: GameMode,
+    },
+    /// Moving on to the next story of an All Stories run
+    StoryStarted(GameMode),
+    BlackDragon,
+    /// Obtaining the given Chaos Emerald, by clearing a Special Stage
+    Emerald(u32),
+}
+
+/// Lists the steps of the route, in the order the game plays them. An All Stories run goes through
+/// every story, otherwise the route only covers the story being played. Only the splits enabled
+/// in the settings are part of it, so the route always matches the splits the runner asked for.
+fn steps(settings: &Settings, game_mode: GameMode) -> impl Iterator<Item = RouteStep> + '_ {
+    let all_stories = settings.all_stories;
+    let game_modes: &[GameMode] = match game_mode {
+        _ if all_stories => &[GameMode::Story, GameMode::TripStory],
+        GameMode::Story => &[GameMode::Story],
+        GameMode::TripStory => &[GameMode::TripStory],
+        _ => &[],
+    };
+    let last_story = all_stories || game_mode == GameMode::LastStory;
+
+    game_modes
+        .iter()
+        .flat_map(move |&game_mode| {
+            let story_started =
+                (all_stories && game_mode == GameMode::TripStory && settings.all_stories_trip)
+                    .then_some(RouteStep::StoryStarted(game_mode));
+
+            story_started.into_iter().chain(
+                STAGES
+                    .iter()
+                    .filter(move |stage| {
+                        stage
+                            .variant(game_mode)
+                            .is_some_and(|variant| (variant.enabled)(settings))
+                    })
+                    .map(move |stage| RouteStep::Act {
+                        level_id: stage.id,
+                        game_mode,
+                    }),
+            )
+        })
+        .chain(
+            (all_stories && settings.all_stories_last_story)
+                .then_some(RouteStep::StoryStarted(GameMode::LastStory)),
+        )
+        .chain((last_story && settings.black_dragon).then_some(RouteStep::BlackDragon))
+}
+
+/// Lists the emeralds the route splits on. Special Stages can be entered from any act, so where
+/// they fall among the acts depends on the run. They're kept in a sequence of their own instead,
+/// in the order the emeralds are obtained.
+fn emerald_steps(settings: &Settings) -> impl Iterator<Item = RouteStep> + '_ {
+    let enabled = [
+        settings.emerald_1,
+        settings.emerald_2,
+        settings.emerald_3,
+        settings.emerald_4,
+        settings.emerald_5,
+        settings.emerald_6,
+        settings.emerald_7,
+    ];
+
+    (1..)
+        .zip(enabled)
+        .filter(move |&(_, enabled)| settings.route && (enabled || settings.special_stage_exit))
+        .map(|(emerald, _)| RouteStep::Emerald(emerald))
+}
+
+/// How far into the route the current attempt is
+#[derive(Default, Copy, Clone)]
+pub struct RouteProgress {
+    position: usize,
+    emerald_position: usize,
+    /// Splits that have been skipped over, which still need to be skipped on the timer
+    pending_skips: usize,
+}
+
+impl RouteProgress {
+    /// Moves on to the next step of the route if `step` is the one we were expecting, and
+    /// returns whether to split. Steps happening out of order are handled according to the skip
+    /// policy.
+    pub fn advance(&mut self, settings: &Settings, game_mode: GameMode, step: RouteStep) -> bool {
+        let (ahead, position) = match step {
+            RouteStep::Emerald(_) => (
+                emerald_steps(settings)
+                    .skip(self.emerald_position)
+                    .position(|val| val == step),
+                &mut self.emerald_position,
+            ),
+            _ => (
+                steps(settings, game_mode)
+                    .skip(self.position)
+                    .position(|val| val == step),
+                &mut self.position,
+            ),
+        };
+
+        match ahead {
+            Some(0) => {
+                *position += 1;
+                true
+            }
+            Some(ahead) if settings.route_skip == RouteSkip::SkipAhead => {
+                *position += ahead + 1;
+                self.pending_skips += ahead;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Returns how many splits need to be skipped on the timer before the next split
+    pub fn take_skips(&mut self) -> usize {
+        core::mem::take(&mut self.pending_skips)
+    }
+}
@@ -0,0 +1,395 @@
+use crate::{game_mode::GameMode, Settings};
+
+/// A single act, as identified by the ID stored in the `stageInfo` of the `GameSceneControllerBase`
+pub struct Stage {
+    pub id: u32,
+    pub zone: &'static str,
+    pub story: StageVariant,
+    pub trip: StageVariant,
+    /// For boss acts, the setting choosing whether to split on the boss kill instead of the results screen
+    pub boss_kill: Option<fn(&Settings) -> bool>,
+}
+
+/// How an act is presented in a specific story.
+/// Some acts share the same ID, but are labeled differently between Story Mode and Trip's Story.
+pub struct StageVariant {
+    pub act: &'static str,
+    pub enabled: fn(&Settings) -> bool,
+}
+
+impl Stage {
+    /// Looks up an act in the catalogue by its level ID
+    pub fn find(level_id: u32) -> Option<&'static Self> {
+        STAGES.iter().find(|stage| stage.id == level_id)
+    }
+
+    /// Returns how this act is presented in the given game mode, if it belongs to any story
+    pub const fn variant(&self, game_mode: GameMode) -> Option<&StageVariant> {
+        match game_mode {
+            GameMode::Story => Some(&self.story),
+            GameMode::TripStory => Some(&self.trip),
+            _ => None,
+        }
+    }
+
+    /// Whether the act has been set to split on the boss kill
+    pub fn splits_on_boss_kill(&self, settings: &Settings) -> bool {
+        self.boss_kill.is_some_and(|enabled| enabled(settings))
+    }
+}
+
+/// Zone bosses are named after the zone they're fought in, like `Bos111` and `Bos112` for the two
+/// phases of the final boss in Egg Fortress Zone (zone 11). Mid-bosses, which also inherit from
+/// `EnemySpecialBase`, don't follow this pattern.
+pub fn is_zone_boss(class_name: &[u8], zone: u32) -> bool {
+    match class_name {
+        [b'B', b'o', b's', tens, units, number, ..] => {
+            zone < 100
+                && u32::from(tens.wrapping_sub(b'0')) == zone / 10
+                && u32::from(units.wrapping_sub(b'0')) == zone % 10
+                && number.is_ascii_digit()
+        }
+        _ => false,
+    }
+}
+
+pub static STAGES: &[Stage] = &[
+    Stage {
+        id: 10100,
+        zone: "Bridge Island Zone",
+        story: StageVariant {
+            act: "Act 1",
+            enabled: |s| s.bridge_island_1,
+        },
+        trip: StageVariant {
+            act: "Act 1",
+            enabled: |s| s.trip_bridge_island_1,
+        },
+        boss_kill: None,
+    },
+    Stage {
+        id: 10200,
+        zone: "Bridge Island Zone",
+        story: StageVariant {
+            act: "Act 2",
+            enabled: |s| s.bridge_island_2,
+        },
+        trip: StageVariant {
+            act: "Act 2",
+            enabled: |s| s.trip_bridge_island_2,
+        },
+        boss_kill: Some(|s| s.boss_bridge_island_2),
+    },
+    Stage {
+        id: 600102,
+        zone: "Bridge Island Zone",
+        story: StageVariant {
+            act: "Act Fruit",
+            enabled: |s| s.bridge_island_fruit,
+        },
+        trip: StageVariant {
+            act: "Act Fruit",
+            enabled: |s| s.trip_bridge_island_fruit,
+        },
+        boss_kill: None,
+    },
+    Stage {
+        id: 20100,
+        zone: "Speed Jungle Zone",
+        story: StageVariant {
+            act: "Act 1",
+            enabled: |s| s.speed_jungle_1,
+        },
+        trip: StageVariant {
+            act: "Act 1",
+            enabled: |s| s.trip_speed_jungle_1,
+        },
+        boss_kill: None,
+    },
+    Stage {
+        id: 20200,
+        zone: "Speed Jungle Zone",
+        story: StageVariant {
+            act: "Act Sonic",
+            enabled: |s| s.speed_jungle_sonic,
+        },
+        trip: StageVariant {
+            act: "Act 2",
+            enabled: |s| s.trip_speed_jungle_2,
+        },
+        boss_kill: None,
+    },
+    Stage {
+        id: 20300,
+        zone: "Speed Jungle Zone",
+        story: StageVariant {
+            act: "Act 2",
+            enabled: |s| s.speed_jungle_2,
+        },
+        trip: StageVariant {
+            act: "Act 3",
+            enabled: |s| s.trip_speed_jungle_3,
+        },
+        boss_kill: Some(|s| s.boss_speed_jungle_2),
+    },
+    Stage {
+        id: 30100,
+        zone: "Sky Temple Zone",
+        story: StageVariant {
+            act: "Act 1",
+            enabled: |s| s.sky_temple_1,
+        },
+        trip: StageVariant {
+            act: "Act 1",
+            enabled: |s| s.trip_sky_temple_1,
+        },
+        boss_kill: Some(|s| s.boss_sky_temple_1),
+    },
+    Stage {
+        id: 40100,
+        zone: "Pinball Carnival Zone",
+        story: StageVariant {
+            act: "Act 1",
+            enabled: |s| s.pinball_carnival_1,
+        },
+        trip: StageVariant {
+            act: "Act 1",
+            enabled: |s| s.trip_pinball_carnival_1,
+        },
+        boss_kill: None,
+    },
+    Stage {
+        id: 40200,
+        zone: "Pinball Carnival Zone",
+        story: StageVariant {
+            act: "Act 2",
+            enabled: |s| s.pinball_carnival_2,
+        },
+        trip: StageVariant {
+            act: "Act 2",
+            enabled: |s| s.trip_pinball_carnival_2,
+        },
+        boss_kill: Some(|s| s.boss_pinball_carnival_2),
+    },
+    Stage {
+        id: 600401,
+        zone: "Pinball Carnival Zone",
+        story: StageVariant {
+            act: "Act Fruit",
+            enabled: |s| s.pinball_carnival_fruit,
+        },
+        trip: StageVariant {
+            act: "Act Fruit",
+            enabled: |s| s.trip_pinball_carnival_fruit,
+        },
+        boss_kill: None,
+    },
+    Stage {
+        id: 50100,
+        zone: "Lagoon City Zone",
+        story: StageVariant {
+            act: "Act 1",
+            enabled: |s| s.lagoon_city_1,
+        },
+        trip: StageVariant {
+            act: "Act 1",
+            enabled: |s| s.trip_lagoon_city_1,
+        },
+        boss_kill: None,
+    },
+    Stage {
+        id: 50200,
+        zone: "Lagoon City Zone",
+        story: StageVariant {
+            act: "Act Amy",
+            enabled: |s| s.lagoon_city_amy,
+        },
+        trip: StageVariant {
+            act: "Act 2",
+            enabled: |s| s.trip_lagoon_city_2,
+        },
+        boss_kill: None,
+    },
+    Stage {
+        id: 50300,
+        zone: "Lagoon City Zone",
+        story: StageVariant {
+            act: "Act 2",
+            enabled: |s| s.lagoon_city_2,
+        },
+        trip: StageVariant {
+            act: "Act 3",
+            enabled: |s| s.trip_lagoon_city_3,
+        },
+        boss_kill: Some(|s| s.boss_lagoon_city_2),
+    },
+    Stage {
+        id: 60100,
+        zone: "Sand Sanctuary Zone",
+        story: StageVariant {
+            act: "Act 1",
+            enabled: |s| s.sand_sanctuary_1,
+        },
+        trip: StageVariant {
+            act: "Act 1",
+            enabled: |s| s.trip_sand_sanctuary_1,
+        },
+        boss_kill: Some(|s| s.boss_sand_sanctuary_1),
+    },
+    Stage {
+        id: 70100,
+        zone: "Press Factory Zone",
+        story: StageVariant {
+            act: "Act 1",
+            enabled: |s| s.press_factory_1,
+        },
+        trip: StageVariant {
+            act: "Act 1",
+            enabled: |s| s.trip_press_factory_1,
+        },
+        boss_kill: None,
+    },
+    Stage {
+        id: 70200,
+        zone: "Press Factory Zone",
+        story: StageVariant {
+            act: "Act 2",
+            enabled: |s| s.press_factory_2,
+        },
+        trip: StageVariant {
+            act: "Act 2",
+            enabled: |s| s.trip_press_factory_2,
+        },
+        boss_kill: Some(|s| s.boss_press_factory_2),
+    },
+    Stage {
+        id: 600702,
+        zone: "Press Factory Zone",
+        story: StageVariant {
+            act: "Act Fruit",
+            enabled: |s| s.press_factory_fruit,
+        },
+        trip: StageVariant {
+            act: "Act Fruit",
+            enabled: |s| s.trip_press_factory_fruit,
+        },
+        boss_kill: None,
+    },
+    Stage {
+        id: 80100,
+        zone: "Golden Capital Zone",
+        story: StageVariant {
+            act: "Act 1",
+            enabled: |s| s.golden_capital_1,
+        },
+        trip: StageVariant {
+            act: "Act 1",
+            enabled: |s| s.trip_golden_capital_1,
+        },
+        boss_kill: None,
+    },
+    Stage {
+        id: 80200,
+        zone: "Golden Capital Zone",
+        story: StageVariant {
+            act: "Act Knuckles",
+            enabled: |s| s.golden_capital_knuckles,
+        },
+        trip: StageVariant {
+            act: "Act 2",
+            enabled: |s| s.trip_golden_capital_2,
+        },
+        boss_kill: None,
+    },
+    Stage {
+        id: 80300,
+        zone: "Golden Capital Zone",
+        story: StageVariant {
+            act: "Act 2",
+            enabled: |s| s.golden_capital_2,
+        },
+        trip: StageVariant {
+            act: "Act 3",
+            enabled: |s| s.trip_golden_capital_3,
+        },
+        boss_kill: Some(|s| s.boss_golden_capital_2),
+    },
+    Stage {
+        id: 90100,
+        zone: "Cyber Station Zone",
+        story: StageVariant {
+            act: "Act 1",
+            enabled: |s| s.cyber_station_1,
+        },
+        trip: StageVariant {
+            act: "Act 1",
+            enabled: |s| s.trip_cyber_station_1,
+        },
+        boss_kill: Some(|s| s.boss_cyber_station_1),
+    },
+    Stage {
+        id: 100100,
+        zone: "Frozen Base Zone",
+        story: StageVariant {
+            act: "Act 1",
+            enabled: |s| s.frozen_base_1,
+        },
+        trip: StageVariant {
+            act: "Act 1",
+            enabled: |s| s.trip_frozen_base_1,
+        },
+        boss_kill: None,
+    },
+    Stage {
+        id: 100200,
+        zone: "Frozen Base Zone",
+        story: StageVariant {
+            act: "Act Tails",
+            enabled: |s| s.frozen_base_tails,
+        },
+        trip: StageVariant {
+            act: "Act 2",
+            enabled: |s| s.trip_frozen_base_2,
+        },
+        boss_kill: None,
+    },
+    Stage {
+        id: 100300,
+        zone: "Frozen Base Zone",
+        story: StageVariant {
+            act: "Act 2",
+            enabled: |s| s.frozen_base_2,
+        },
+        trip: StageVariant {
+            act: "Act 3",
+            enabled: |s| s.trip_frozen_base_3,
+        },
+        boss_kill: Some(|s| s.boss_frozen_base_2),
+    },
+    Stage {
+        id: 110100,
+        zone: "Egg Fortress Zone",
+        story: StageVariant {
+            act: "Act 1",
+            enabled: |s| s.egg_fortress_1,
+        },
+        trip: StageVariant {
+            act: "Act 1",
+            enabled: |s| s.trip_egg_fortress_1,
+        },
+        boss_kill: Some(|s| s.boss_egg_fortress_1),
+    },
+    Stage {
+        id: 110200,
+        zone: "Egg Fortress Zone",
+        story: StageVariant {
+            act: "Act 2",
+            enabled: |s| s.egg_fortress_2,
+        },
+        trip: StageVariant {
+            act: "Act 2",
+            enabled: |s| s.trip_egg_fortress_2,
+        },
+        boss_kill: None,
+    },
+];
@@ -0,0 +1,1353 @@
+use std::collections::HashMap;
+
+use asr::{time::Duration, Address};
+use bytemuck::{CheckedBitPattern, NoUninit};
+
+use crate::{
+    class_hierarchy::derives_from,
+    events::GameEvent,
+    game_mode::GameMode,
+    late_binding::LateField,
+    memory_source::{Error, MemorySource, PointerPath},
+    progress::RunProgress,
+    stages::STAGES,
+    trace::replay::{replay, Action},
+    BRMainGameSceneController, EnemySpecialBase, GameSceneControllerOffsets, Memory, RouteSkip,
+    Settings, SplitTiming, SysSaveDataStory, TimingMethod, Watchers, ENEMY_BASE_TYPE_FALLBACK,
+};
+
+// Layout of the fake game memory. The offsets are arbitrary, as long as they don't overlap.
+const SCENE_MANAGER_STATIC: u64 = 0x1000;
+const GAME_MANAGER_STATIC: u64 = 0x2000;
+const GAME_MANAGER: u64 = 0x2100;
+const SCENE_MANAGER_PARENT_STATIC: u64 = 0x3000;
+const SCENE_MANAGER: u64 = 0x3100;
+const SCENE_CONTROLLER: u64 = 0x4000;
+const SCENE_CONTROLLER_BASE_CLASS: u64 = 0x4100;
+const SCENE_CONTROLLER_BASE_NAME: u64 = 0x4200;
+const STAGE_INFO: u64 = 0x5000;
+const BOSS: u64 = 0x6000;
+const BOSS_BASE_CLASS: u64 = 0x6300;
+const BOSS_BASE_NAME: u64 = 0x6400;
+const SAVE_MANAGER_STATIC: u64 = 0x7000;
+const SAVE_MANAGER: u64 = 0x7100;
+const SYS_SAVE_DATA: u64 = 0x7200;
+const SAVE_DATAS: u64 = 0x7300;
+const SAVE_SLOT: u64 = 0x7400;
+const SAVE_SLOT_2: u64 = 0x7500;
+/// Save data replacing the one of the first slot when a new game is created
+const NEW_SAVE_SLOT: u64 = 0x7600;
+/// Each scene controller class gets its own block here, with its name in the second half
+const SCENE_CONTROLLER_CLASSES: u64 = 0x10000;
+/// Same for the classes of the bosses
+const BOSS_CLASSES: u64 = 0x20000;
+
+/// An in-memory stand-in for the game process
+#[derive(Default)]
+struct FakeGame {
+    memory: HashMap<u64, u8>,
+}
+
+impl FakeGame {
+    fn write<T: NoUninit>(&mut self, address: u64, value: T) {
+        for (i, &byte) in bytemuck::bytes_of(&value).iter().enumerate() {
+            self.memory.insert(address + i as u64, byte);
+        }
+    }
+
+    fn write_name(&mut self, address: u64, name: &str) {
+        let mut buf = [0u8; 128];
+        buf[..name.len()].copy_from_slice(name.as_bytes());
+        self.write(address, buf);
+    }
+}
+
+impl MemorySource for FakeGame {
+    fn read<T: CheckedBitPattern>(&self, address: impl Into<Address>) -> Result<T, Error> {
+        let address = address.into().value();
+        let bytes = (0..core::mem::size_of::<T>() as u64)
+            .map(|i| self.memory.get(&(address + i)).copied().ok_or(Error))
+            .collect::<Result<Vec<u8>, Error>>()?;
+        bytemuck::checked::try_pod_read_unaligned(&bytes).map_err(|_| Error)
+    }
+}
+
+/// Ties the fake game together with the state the autosplitter keeps between ticks
+struct Harness {
+    game: FakeGame,
+    memory: Memory,
+    watchers: Watchers,
+    settings: Settings,
+    scene_classes: Vec<String>,
+    boss_classes: Vec<String>,
+}
+
+/// Returns the index of the class with the given name, adding it if it's new
+fn class_index(classes: &mut Vec<String>, class_name: &str) -> u64 {
+    let index = match classes.iter().position(|val| val == class_name) {
+        Some(index) => index,
+        None => {
+            classes.push(class_name.to_owned());
+            classes.len() - 1
+        }
+    };
+    index as u64
+}
+
+impl Harness {
+    fn new() -> Self {
+        let mut game = FakeGame::default();
+
+        game.write(GAME_MANAGER_STATIC, GAME_MANAGER);
+        game.write(SCENE_MANAGER_PARENT_STATIC, SCENE_MANAGER);
+        game.write(SCENE_MANAGER + 0x18, SCENE_CONTROLLER);
+        game.write(
+            SCENE_CONTROLLER_BASE_CLASS + 0x10,
+            SCENE_CONTROLLER_BASE_NAME,
+        );
+        game.write(SCENE_CONTROLLER_BASE_CLASS + 0x58, 0u64);
+        game.write_name(SCENE_CONTROLLER_BASE_NAME, "GameSceneControllerBase");
+        game.write(SCENE_CONTROLLER + 0x20, STAGE_INFO);
+        game.write(SCENE_CONTROLLER + 0x30, [0u8; 8]);
+        game.write(SCENE_CONTROLLER + 0x38, BOSS);
+        game.write(BOSS_BASE_CLASS + 0x10, BOSS_BASE_NAME);
+        game.write(BOSS_BASE_CLASS + 0x58, 0u64);
+        game.write_name(BOSS_BASE_NAME, "EnemySpecialBase");
+        game.write(SAVE_MANAGER_STATIC, SAVE_MANAGER);
+        game.write(SAVE_MANAGER + 0x10, SYS_SAVE_DATA);
+        game.write(SAVE_MANAGER + 0x20, 0u32);
+        game.write(SYS_SAVE_DATA + 0x18, SAVE_DATAS);
+        game.write(SAVE_DATAS + 0x20, SAVE_SLOT);
+        game.write(SAVE_DATAS + 0x28, SAVE_SLOT_2);
+        game.write(SAVE_SLOT_2 + 0x10, 1u8);
+        game.write(SAVE_SLOT_2 + 0x11, 1u8);
+
+        let memory = Memory {
+            is_loading: PointerPath::new(Address::new(SCENE_MANAGER_STATIC), [0x8]),
+            game_mode: PointerPath::new(Address::new(GAME_MANAGER_STATIC), [0, 0x10]),
+            save_data: SysSaveDataStory {
+                static_table: Address::new(SAVE_MANAGER_STATIC),
+                instance: 0,
+                sys_save_data: 0x10,
+                save_datas: 0x18,
+                current_slot: 0x20,
+                is_normal_first_play: 0x10,
+                is_trip_first_play: 0x11,
+                emerald_flags: Some(0x18),
+            },
+            current_scene_controller: PointerPath::new(
+                Address::new(SCENE_MANAGER_PARENT_STATIC),
+                [0, 0x18],
+            ),
+            game_scene_controller_offsets: GameSceneControllerOffsets {
+                stage_info: 0x20,
+                stage_time: 0x28,
+                is_goal_sequence: 0x30,
+                is_result_sequence: 0x31,
+                is_time_attack_mode: 0x32,
+                active_boss_base: 0x38,
+            },
+            boss_controller_offsets: EnemySpecialBase {
+                base_type: LateField::resolved(0x130),
+            },
+            battle_offsets: BRMainGameSceneController {
+                round: LateField::resolved(0x40),
+                placement: LateField::resolved(0x44),
+            },
+            resolver: None,
+        };
+
+        let mut harness = Self {
+            game,
+            memory,
+            watchers: Watchers::default(),
+            settings: default_settings(),
+            scene_classes: Vec::new(),
+            boss_classes: Vec::new(),
+        };
+
+        harness.set_loading(false);
+        harness.set_game_mode(0);
+        harness.set_scene("GameSceneController");
+        harness.set_level(0);
+        harness.set_goal(false, false);
+        harness.set_time_attack(false);
+        harness.set_boss("", 0);
+        harness.set_first_play(false, false);
+        harness.set_emeralds(0);
+        harness.tick();
+        harness
+    }
+
+    fn tick(&mut self) {
+        crate::update_loop(&self.game, &self.memory, &mut self.watchers, &self.settings);
+    }
+
+    fn start(&self) -> bool {
+        crate::start(&self.watchers, &self.settings)
+    }
+
+    fn split(&mut self) -> bool {
+        crate::split(&mut self.watchers, &self.settings)
+    }
+
+    fn reset(&self) -> bool {
+        crate::reset(&self.watchers, &self.settings)
+    }
+
+    fn game_time(&self) -> Option<Duration> {
+        crate::game_time(&self.watchers, &self.settings)
+    }
+
+    fn set_loading(&mut self, is_loading: bool) {
+        self.game
+            .write(SCENE_MANAGER_STATIC + 0x8, is_loading as u8);
+    }
+
+    fn set_game_mode(&mut self, game_mode: u32) {
+        self.game.write(GAME_MANAGER + 0x10, game_mode);
+    }
+
+    /// Switches to a scene controller of the given class, which is created the first time it's used.
+    /// Only the classes named like a game scene inherit from `GameSceneControllerBase`.
+    fn set_scene(&mut self, class_name: &str) {
+        let class =
+            SCENE_CONTROLLER_CLASSES + 0x100 * class_index(&mut self.scene_classes, class_name);
+        let parent = if class_name.ends_with("GameSceneController") {
+            SCENE_CONTROLLER_BASE_CLASS
+        } else {
+            0
+        };
+        self.game.write(class + 0x10, class + 0x80);
+        self.game.write(class + 0x58, parent);
+        self.game.write_name(class + 0x80, class_name);
+        self.game.write(SCENE_CONTROLLER, class);
+    }
+
+    fn set_stage_time(&mut self, seconds: f32) {
+        self.game.write(SCENE_CONTROLLER + 0x28, seconds);
+    }
+
+    fn set_level(&mut self, level_id: u32) {
+        self.game.write(STAGE_INFO + 0x10, level_id);
+    }
+
+    fn set_goal(&mut self, is_goal_sequence: bool, is_result_sequence: bool) {
+        self.game
+            .write(SCENE_CONTROLLER + 0x30, is_goal_sequence as u8);
+        self.game
+            .write(SCENE_CONTROLLER + 0x31, is_result_sequence as u8);
+    }
+
+    fn set_time_attack(&mut self, is_time_attack: bool) {
+        self.game
+            .write(SCENE_CONTROLLER + 0x32, is_time_attack as u8);
+    }
+
+    /// Switches to a boss of the given class, which is created the first time it's used
+    fn set_boss(&mut self, class_name: &str, base_type: u8) {
+        let class = BOSS_CLASSES + 0x100 * class_index(&mut self.boss_classes, class_name);
+        self.game.write(class + 0x10, class + 0x80);
+        self.game.write(class + 0x58, BOSS_BASE_CLASS);
+        self.game.write_name(class + 0x80, class_name);
+        self.game.write(BOSS, class);
+        self.game.write(BOSS + 0x130, base_type);
+    }
+
+    fn set_battle_round(&mut self, round: u32) {
+        self.game.write(SCENE_CONTROLLER + 0x40, round);
+    }
+
+    fn set_emeralds(&mut self, count: u32) {
+        self.game
+            .write(SAVE_SLOT + 0x18, ((1u16 << count) - 1) as u8);
+    }
+
+    /// Goes through a Special Stage, coming back with the given amount of emeralds
+    fn clear_special_stage(&mut self, emeralds: u32) -> bool {
+        self.set_scene("SpecialStageGameSceneController");
+        self.tick();
+        self.set_emeralds(emeralds);
+        self.tick();
+        self.set_scene("GameSceneController");
+        self.tick();
+        self.split()
+    }
+
+    fn set_first_play(&mut self, is_normal_first_play: bool, is_trip_first_play: bool) {
+        self.game
+            .write(SAVE_SLOT + 0x10, is_normal_first_play as u8);
+        self.game.write(SAVE_SLOT + 0x11, is_trip_first_play as u8);
+    }
+
+    /// Moves to another save slot. Slot 0 is the one the other helpers play on, while slot 1
+    /// holds a save that hasn't been started yet.
+    fn set_current_slot(&mut self, slot: u32) {
+        self.game.write(SAVE_MANAGER + 0x20, slot);
+    }
+
+    /// Plays through an act, from the goal ring to the end of the results screen
+    fn clear_act(&mut self) -> bool {
+        self.set_goal(true, false);
+        self.tick();
+        let mut split = self.split();
+        self.set_goal(false, true);
+        self.tick();
+        split |= self.split();
+        self.set_goal(false, false);
+        self.tick();
+        split | self.split()
+    }
+}
+
+fn default_settings() -> Settings {
+    Settings {
+        timing_method: TimingMethod::LoadRemoved,
+        split_timing: SplitTiming::ResultsEnd,
+        repeat_splits: false,
+        record_trace: false,
+        verbose_logging: false,
+        start_story: true,
+        start_trip: true,
+        start_last_story: true,
+        reset_new_save: true,
+        reset_title_screen: false,
+        reset_last_story: true,
+        _time_attack: false,
+        time_attack: false,
+        _il: false,
+        il_mode: false,
+        _battle: false,
+        battle_mode: false,
+        battle_round: true,
+        _all_stories: false,
+        all_stories: false,
+        all_stories_trip: true,
+        all_stories_last_story: true,
+        _route: false,
+        route: false,
+        route_skip: RouteSkip::Ignore,
+        _story: false,
+        bridge_island_1: true,
+        bridge_island_2: true,
+        bridge_island_fruit: true,
+        speed_jungle_1: true,
+        speed_jungle_sonic: true,
+        speed_jungle_2: true,
+        sky_temple_1: true,
+        pinball_carnival_1: true,
+        pinball_carnival_2: true,
+        pinball_carnival_fruit: true,
+        lagoon_city_1: true,
+        lagoon_city_amy: true,
+        lagoon_city_2: true,
+        sand_sanctuary_1: true,
+        press_factory_1: true,
+        press_factory_2: true,
+        press_factory_fruit: true,
+        golden_capital_1: true,
+        golden_capital_knuckles: true,
+        golden_capital_2: true,
+        cyber_station_1: true,
+        frozen_base_1: true,
+        frozen_base_tails: true,
+        frozen_base_2: true,
+        egg_fortress_1: true,
+        egg_fortress_2: true,
+        _trip: false,
+        trip_bridge_island_1: true,
+        trip_bridge_island_2: true,
+        trip_bridge_island_fruit: true,
+        trip_speed_jungle_1: true,
+        trip_speed_jungle_2: true,
+        trip_speed_jungle_3: true,
+        trip_sky_temple_1: true,
+        trip_pinball_carnival_1: true,
+        trip_pinball_carnival_2: true,
+        trip_pinball_carnival_fruit: true,
+        trip_lagoon_city_1: true,
+        trip_lagoon_city_2: true,
+        trip_lagoon_city_3: true,
+        trip_sand_sanctuary_1: true,
+        trip_press_factory_1: true,
+        trip_press_factory_2: true,
+        trip_press_factory_fruit: true,
+        trip_golden_capital_1: true,
+        trip_golden_capital_2: true,
+        trip_golden_capital_3: true,
+        trip_cyber_station_1: true,
+        trip_frozen_base_1: true,
+        trip_frozen_base_2: true,
+        trip_frozen_base_3: true,
+        trip_egg_fortress_1: true,
+        trip_egg_fortress_2: true,
+        _final_story: false,
+        black_dragon: true,
+        _boss_kill: false,
+        boss_bridge_island_2: false,
+        boss_speed_jungle_2: false,
+        boss_sky_temple_1: false,
+        boss_pinball_carnival_2: false,
+        boss_lagoon_city_2: false,
+        boss_sand_sanctuary_1: false,
+        boss_press_factory_2: false,
+        boss_golden_capital_2: false,
+        boss_cyber_station_1: false,
+        boss_frozen_base_2: false,
+        boss_egg_fortress_1: false,
+        _emeralds: false,
+        special_stage_exit: false,
+        emerald_1: false,
+        emerald_2: false,
+        emerald_3: false,
+        emerald_4: false,
+        emerald_5: false,
+        emerald_6: false,
+        emerald_7: false,
+    }
+}
+
+#[test]
+fn story_starts_on_new_save() {
+    let mut harness = Harness::new();
+    harness.set_first_play(true, true);
+    harness.tick();
+    assert!(!harness.start());
+
+    harness.set_first_play(false, true);
+    harness.tick();
+    assert!(harness.start());
+}
+
+#[test]
+fn empty_save_slots_are_picked_up_once_filled_in() {
+    let mut harness = Harness::new();
+    harness.game.write(SAVE_DATAS + 0x20, 0u64);
+    harness.tick();
+
+    harness.game.write(SAVE_DATAS + 0x20, SAVE_SLOT);
+    harness.set_first_play(true, true);
+    harness.tick();
+    assert!(!harness.start());
+
+    harness.set_first_play(false, true);
+    harness.tick();
+    assert!(harness.start());
+}
+
+#[test]
+fn replaced_save_slots_are_picked_up() {
+    let mut harness = Harness::new();
+    harness.game.write(NEW_SAVE_SLOT + 0x10, 1u8);
+    harness.game.write(NEW_SAVE_SLOT + 0x11, 1u8);
+    harness.game.write(SAVE_DATAS + 0x20, NEW_SAVE_SLOT);
+    harness.tick();
+    assert!(!harness.start());
+
+    harness.game.write(NEW_SAVE_SLOT + 0x10, 0u8);
+    harness.tick();
+    assert!(harness.start());
+}
+
+#[test]
+fn il_mode_starts_and_splits_on_each_attempt() {
+    let mut harness = Harness::new();
+    harness.settings.il_mode = true;
+    harness.set_level(10100);
+    harness.tick();
+    assert!(harness.start());
+
+    harness.set_stage_time(12.0);
+    harness.tick();
+    assert!(!harness.reset());
+    assert!(harness.clear_act());
+
+    // Restarting the act
+    harness.set_stage_time(0.0);
+    harness.tick();
+    assert!(harness.reset());
+    assert!(harness.start());
+}
+
+#[test]
+fn il_mode_doesnt_restart_when_quitting_to_the_title() {
+    let mut harness = Harness::new();
+    harness.settings.il_mode = true;
+    harness.set_level(10100);
+    harness.set_stage_time(12.0);
+    harness.tick();
+
+    harness.set_scene("TitleSceneController");
+    harness.tick();
+    assert!(!harness.reset());
+    assert!(!harness.start());
+}
+
+#[test]
+fn il_mode_doesnt_restart_in_special_stages() {
+    let mut harness = Harness::new();
+    harness.settings.il_mode = true;
+    harness.set_level(10100);
+    harness.set_stage_time(12.0);
+    harness.tick();
+
+    harness.set_scene("SpecialStageGameSceneController");
+    harness.set_stage_time(0.0);
+    harness.tick();
+    assert!(!harness.reset());
+    assert!(!harness.start());
+
+    harness.set_scene("GameSceneController");
+    harness.set_stage_time(12.5);
+    harness.tick();
+    assert!(!harness.reset());
+    assert!(!harness.start());
+}
+
+#[test]
+fn il_mode_resets_when_exiting_to_the_map() {
+    let mut harness = Harness::new();
+    harness.settings.il_mode = true;
+    harness.set_level(10100);
+    harness.set_stage_time(12.0);
+    harness.tick();
+
+    harness.set_scene("WorldMapGameSceneController");
+    harness.set_stage_time(0.0);
+    harness.tick();
+    assert!(harness.reset());
+    assert!(!harness.start());
+}
+
+#[test]
+fn switching_save_slots_doesnt_start() {
+    let mut harness = Harness::new();
+    harness.set_current_slot(1);
+    harness.tick();
+
+    // Slot 0 holds a save that has been played already
+    harness.set_current_slot(0);
+    harness.tick();
+    assert!(!harness.start());
+    assert!(!harness.reset());
+    assert_eq!(harness.watchers.current_slot.pair.unwrap().current, 0);
+
+    harness.set_current_slot(1);
+    harness.tick();
+    assert!(!harness.start());
+
+    harness.game.write(SAVE_SLOT_2 + 0x10, 0u8);
+    harness.tick();
+    assert!(harness.start());
+}
+
+#[test]
+fn trip_starts_on_new_save() {
+    let mut harness = Harness::new();
+    harness.set_first_play(true, true);
+    harness.tick();
+
+    harness.set_first_play(true, false);
+    harness.tick();
+    assert!(harness.start());
+
+    harness.settings.start_trip = false;
+    assert!(!harness.start());
+}
+
+#[test]
+fn act_clears_produce_events() {
+    let mut harness = Harness::new();
+    harness.set_level(10100);
+    harness.tick();
+    assert!(harness
+        .watchers
+        .events
+        .contains(GameEvent::ActStarted(10100)));
+
+    harness.set_goal(true, false);
+    harness.tick();
+    assert!(harness.watchers.events.contains(GameEvent::GoalReached));
+
+    harness.set_goal(false, true);
+    harness.tick();
+    assert!(harness.watchers.events.contains(GameEvent::ResultsStarted));
+    assert!(!harness.watchers.events.contains(GameEvent::GoalReached));
+
+    harness.set_goal(false, false);
+    harness.tick();
+    assert_eq!(
+        harness.watchers.events.iter().collect::<Vec<_>>(),
+        [GameEvent::ResultsEnded]
+    );
+}
+
+#[test]
+fn story_splits_at_the_end_of_the_results() {
+    let mut harness = Harness::new();
+    harness.set_level(10100);
+    harness.tick();
+
+    harness.set_goal(true, false);
+    harness.tick();
+    assert!(!harness.split());
+
+    harness.set_goal(false, true);
+    harness.tick();
+    assert!(!harness.split());
+
+    harness.set_goal(false, false);
+    harness.tick();
+    assert!(harness.split());
+}
+
+#[test]
+fn game_time_counts_each_act_once() {
+    let mut harness = Harness::new();
+    harness.settings.timing_method = TimingMethod::InGameTime;
+    harness.set_level(10100);
+    harness.set_stage_time(0.0);
+    harness.tick();
+
+    harness.set_stage_time(60.0);
+    harness.tick();
+    assert_eq!(harness.game_time(), Some(Duration::seconds(60)));
+
+    harness.set_goal(true, false);
+    harness.tick();
+    assert_eq!(harness.game_time(), Some(Duration::seconds(60)));
+
+    harness.set_goal(false, true);
+    harness.tick();
+    harness.set_goal(false, false);
+    harness.tick();
+    assert!(harness.watchers.events.contains(GameEvent::ResultsEnded));
+    assert!(harness.split());
+    assert_eq!(harness.game_time(), Some(Duration::seconds(60)));
+
+    harness.set_level(10200);
+    harness.set_stage_time(0.0);
+    harness.tick();
+    harness.set_stage_time(30.0);
+    harness.tick();
+    assert_eq!(harness.game_time(), Some(Duration::seconds(90)));
+}
+
+#[test]
+fn story_respects_the_act_settings() {
+    let mut harness = Harness::new();
+    harness.settings.bridge_island_1 = false;
+    harness.set_level(10100);
+    harness.tick();
+    assert!(!harness.clear_act());
+
+    harness.set_level(10200);
+    harness.tick();
+    assert!(harness.clear_act());
+}
+
+#[test]
+fn story_splits_on_goal_touch() {
+    let mut harness = Harness::new();
+    harness.settings.split_timing = SplitTiming::GoalTouch;
+    harness.set_level(10100);
+    harness.tick();
+
+    harness.set_goal(true, false);
+    harness.tick();
+    assert!(harness.split());
+
+    harness.set_goal(false, true);
+    harness.tick();
+    assert!(!harness.split());
+
+    harness.set_goal(false, false);
+    harness.tick();
+    assert!(!harness.split());
+}
+
+#[test]
+fn story_splits_on_results_start() {
+    let mut harness = Harness::new();
+    harness.settings.split_timing = SplitTiming::ResultsStart;
+    harness.set_level(10100);
+    harness.tick();
+
+    harness.set_goal(true, false);
+    harness.tick();
+    assert!(!harness.split());
+
+    harness.set_goal(true, true);
+    harness.tick();
+    assert!(harness.split());
+}
+
+#[test]
+fn story_splits_when_the_next_act_loads() {
+    let mut harness = Harness::new();
+    harness.settings.split_timing = SplitTiming::NextAct;
+    harness.set_level(10100);
+    harness.tick();
+    assert!(!harness.clear_act());
+
+    harness.set_level(10200);
+    harness.tick();
+    assert!(harness.split());
+
+    // Leaving an act without clearing it doesn't count
+    harness.set_level(600102);
+    harness.tick();
+    assert!(!harness.split());
+}
+
+#[test]
+fn trip_uses_its_own_act_settings() {
+    let mut harness = Harness::new();
+    harness.set_game_mode(1);
+    harness.settings.speed_jungle_sonic = false;
+    harness.set_level(20200);
+    harness.tick();
+    assert!(harness.clear_act());
+
+    harness.settings.trip_speed_jungle_2 = false;
+    harness.set_level(20300);
+    harness.tick();
+    harness.set_level(20200);
+    harness.tick();
+    assert!(!harness.clear_act());
+}
+
+#[test]
+fn acts_only_split_once_per_run() {
+    let mut harness = Harness::new();
+    harness.set_level(10100);
+    harness.tick();
+    assert!(harness.clear_act());
+    assert!(!harness.clear_act());
+
+    // The same act in Trip's Story is a different split
+    harness.set_game_mode(1);
+    harness.tick();
+    assert!(harness.clear_act());
+
+    harness.settings.repeat_splits = true;
+    assert!(harness.clear_act());
+}
+
+#[test]
+fn every_act_is_tracked_on_its_own() {
+    let mut progress = RunProgress::default();
+    for stage in STAGES {
+        assert!(progress.complete_stage(stage, GameMode::Story));
+    }
+    for stage in STAGES {
+        assert!(!progress.complete_stage(stage, GameMode::Story));
+        assert!(progress.complete_stage(stage, GameMode::TripStory));
+    }
+}
+
+#[test]
+fn final_boss_only_splits_once() {
+    let mut harness = Harness::new();
+    harness.set_level(110200);
+    harness.set_boss("Bos112", 0);
+    harness.tick();
+    harness.set_boss("Bos112", 3);
+    harness.tick();
+    assert!(harness.split());
+
+    harness.set_goal(true, false);
+    harness.tick();
+    assert!(!harness.split());
+}
+
+#[test]
+fn route_only_splits_on_the_next_act() {
+    let mut harness = Harness::new();
+    harness.settings.route = true;
+    harness.set_level(10200);
+    harness.tick();
+    assert!(!harness.clear_act());
+
+    harness.set_level(10100);
+    harness.tick();
+    assert!(harness.clear_act());
+
+    // Disabled acts aren't part of the route
+    harness.settings.bridge_island_2 = false;
+    harness.set_level(600102);
+    harness.tick();
+    assert!(harness.clear_act());
+    assert_eq!(harness.watchers.progress.route.take_skips(), 0);
+}
+
+#[test]
+fn route_can_skip_ahead() {
+    let mut harness = Harness::new();
+    harness.settings.route = true;
+    harness.settings.route_skip = RouteSkip::SkipAhead;
+    harness.set_level(20100);
+    harness.tick();
+    assert!(harness.clear_act());
+    assert_eq!(harness.watchers.progress.route.take_skips(), 3);
+
+    harness.set_level(10100);
+    harness.tick();
+    assert!(!harness.clear_act());
+
+    harness.set_level(20200);
+    harness.tick();
+    assert!(harness.clear_act());
+    assert_eq!(harness.watchers.progress.route.take_skips(), 0);
+}
+
+#[test]
+fn route_counts_the_story_transitions_when_skipping_ahead() {
+    let mut harness = Harness::new();
+    harness.settings.route = true;
+    harness.settings.all_stories = true;
+    harness.settings.route_skip = RouteSkip::SkipAhead;
+    harness.set_game_mode(1);
+    harness.set_level(10100);
+    harness.tick();
+    assert!(harness.clear_act());
+
+    let story_acts = STAGES
+        .iter()
+        .filter(|stage| (stage.story.enabled)(&harness.settings))
+        .count();
+    assert_eq!(harness.watchers.progress.route.take_skips(), story_acts + 1);
+}
+
+#[test]
+fn route_only_splits_on_the_black_dragon_when_enabled() {
+    let mut harness = Harness::new();
+    harness.settings.route = true;
+    harness.settings.all_stories = true;
+    harness.settings.route_skip = RouteSkip::SkipAhead;
+    harness.settings.black_dragon = false;
+    harness.set_game_mode(2);
+    harness.tick();
+    assert!(!harness.reset());
+    assert!(harness.split());
+    harness.watchers.progress.route.take_skips();
+
+    harness.set_boss("Bos111", 0);
+    harness.tick();
+    harness.set_boss("Bos111", 3);
+    harness.tick();
+    assert!(!harness.split());
+}
+
+#[test]
+fn route_splits_on_emeralds_in_order() {
+    let mut harness = Harness::new();
+    harness.settings.route = true;
+    harness.settings.emerald_1 = true;
+    harness.settings.emerald_3 = true;
+    assert!(harness.clear_special_stage(1));
+    assert!(!harness.clear_special_stage(2));
+    assert!(harness.clear_special_stage(3));
+
+    // Emeralds don't move the acts of the route along
+    harness.set_level(10100);
+    harness.tick();
+    assert!(harness.clear_act());
+    assert_eq!(harness.watchers.progress.route.take_skips(), 0);
+}
+
+#[test]
+fn special_stages_only_split_when_cleared() {
+    let mut harness = Harness::new();
+    harness.settings.special_stage_exit = true;
+    harness.set_scene("SpecialStageGameSceneController");
+    harness.tick();
+    harness.set_scene("GameSceneController");
+    harness.tick();
+    assert!(!harness.split());
+
+    harness.set_scene("SpecialStageGameSceneController");
+    harness.tick();
+    harness.set_emeralds(1);
+    harness.tick();
+    assert!(!harness.split());
+    harness.set_scene("GameSceneController");
+    harness.tick();
+    assert!(harness.split());
+}
+
+#[test]
+fn emeralds_split_even_when_saved_after_the_special_stage() {
+    let mut harness = Harness::new();
+    harness.settings.emerald_1 = true;
+    harness.set_scene("SpecialStageGameSceneController");
+    harness.tick();
+    harness.set_scene("GameSceneController");
+    harness.tick();
+    assert!(!harness.split());
+
+    harness.set_emeralds(1);
+    harness.tick();
+    assert!(harness.split());
+}
+
+#[test]
+fn loading_a_save_with_emeralds_doesnt_split() {
+    let mut harness = Harness::new();
+    harness.settings.emerald_1 = true;
+    harness.set_emeralds(1);
+    harness.tick();
+    assert!(!harness.split());
+}
+
+#[test]
+fn story_splits_on_the_final_boss_kill() {
+    let mut harness = Harness::new();
+    harness.set_level(110200);
+    harness.set_boss("Bos112", 0);
+    harness.tick();
+    harness.set_level(110200);
+    harness.tick();
+    assert!(!harness.split());
+
+    harness.set_boss("Bos112", 3);
+    harness.tick();
+    assert!(harness.split());
+}
+
+#[test]
+fn bosses_are_detected_through_their_base_class() {
+    let mut harness = Harness::new();
+    harness.set_level(110200);
+    harness.set_boss("Bos113", 0);
+    harness.tick();
+    harness.set_boss("Bos113", 3);
+    harness.tick();
+    assert!(harness.split());
+
+    let mut harness = Harness::new();
+    harness.game.write_name(BOSS_BASE_NAME, "EnemyBase");
+    harness.set_level(110200);
+    harness.set_boss("Bos112", 0);
+    harness.tick();
+    harness.set_boss("Bos112", 3);
+    harness.tick();
+    assert!(!harness.split());
+}
+
+#[test]
+fn boss_kills_use_the_fallback_offset_until_resolved() {
+    let mut harness = Harness::new();
+    harness.memory.boss_controller_offsets.base_type =
+        LateField::new("EnemySpecialBase", "baseType")
+            .with_fallback(Some(ENEMY_BASE_TYPE_FALLBACK));
+    harness.set_level(110200);
+    harness.set_boss("Bos112", 0);
+    harness.tick();
+    harness.set_boss("Bos112", 3);
+    harness.tick();
+    assert!(harness.split());
+
+    let mut harness = Harness::new();
+    harness.memory.boss_controller_offsets.base_type =
+        LateField::new("EnemySpecialBase", "baseType");
+    harness.set_level(110200);
+    harness.set_boss("Bos112", 0);
+    harness.tick();
+    harness.set_boss("Bos112", 3);
+    harness.tick();
+    assert!(!harness.split());
+}
+
+#[test]
+fn mid_bosses_dont_count_as_boss_kills() {
+    let mut harness = Harness::new();
+    harness.set_level(110200);
+    harness.set_boss("Mbs111", 0);
+    harness.tick();
+    harness.set_boss("Mbs111", 3);
+    harness.tick();
+    assert!(!harness.split());
+
+    // Bosses of other zones don't count either
+    harness.set_boss("Bos011", 0);
+    harness.tick();
+    harness.set_boss("Bos011", 3);
+    harness.tick();
+    assert!(!harness.split());
+}
+
+#[test]
+fn boss_acts_split_on_the_results_when_the_boss_kill_went_unnoticed() {
+    let mut harness = Harness::new();
+    harness.settings.boss_bridge_island_2 = true;
+    harness.set_level(10200);
+    harness.set_boss("Bos011", 0);
+    harness.tick();
+    harness.set_boss("Bos011", 3);
+    harness.tick();
+    assert!(harness.split());
+    assert!(!harness.clear_act());
+
+    let mut harness = Harness::new();
+    harness.settings.boss_bridge_island_2 = true;
+    harness.set_level(10200);
+    harness.tick();
+    assert!(harness.clear_act());
+}
+
+#[test]
+fn last_story_starts_and_splits_on_the_black_dragon() {
+    let mut harness = Harness::new();
+    harness.set_game_mode(2);
+    harness.tick();
+    assert!(harness.start());
+
+    harness.set_scene("BlackDragonBattleGameSceneController");
+    harness.set_boss("Bos111", 0);
+    harness.tick();
+    assert!(!harness.split());
+
+    harness.set_boss("Bos111", 3);
+    harness.tick();
+    assert!(harness.split());
+
+    harness.settings.black_dragon = false;
+    harness.set_boss("Bos111", 0);
+    harness.tick();
+    harness.set_boss("Bos111", 3);
+    harness.tick();
+    assert!(!harness.split());
+}
+
+#[test]
+fn last_story_resets_when_reentered() {
+    let mut harness = Harness::new();
+    harness.set_game_mode(2);
+    harness.tick();
+    assert!(harness.reset());
+
+    harness.settings.reset_last_story = false;
+    assert!(!harness.reset());
+}
+
+#[test]
+fn reattaching_mid_run_waits_for_the_next_act() {
+    let mut harness = Harness::new();
+    harness.settings.all_stories = true;
+    harness.watchers = Watchers {
+        resuming: true,
+        ..Default::default()
+    };
+    harness.set_level(10100);
+    harness.tick();
+
+    // The values go through their defaults while the game boots up
+    harness.set_game_mode(2);
+    harness.tick();
+    assert!(!harness.split());
+    assert!(!harness.reset());
+    harness.set_game_mode(0);
+    harness.tick();
+
+    harness.set_level(10200);
+    harness.tick();
+    assert!(!harness.watchers.resuming);
+    assert!(harness.clear_act());
+}
+
+#[test]
+fn all_stories_only_starts_on_story_mode() {
+    let mut harness = Harness::new();
+    harness.settings.all_stories = true;
+    harness.set_first_play(true, true);
+    harness.tick();
+
+    harness.set_first_play(false, false);
+    harness.tick();
+    assert!(harness.start());
+
+    harness.set_first_play(true, true);
+    harness.tick();
+    harness.set_first_play(true, false);
+    harness.tick();
+    assert!(!harness.start());
+
+    harness.set_game_mode(2);
+    harness.tick();
+    assert!(!harness.start());
+}
+
+#[test]
+fn all_stories_splits_between_stories() {
+    let mut harness = Harness::new();
+    harness.settings.all_stories = true;
+    harness.set_first_play(false, true);
+    harness.tick();
+
+    harness.set_first_play(false, false);
+    harness.tick();
+    assert!(harness.split());
+    assert!(!harness.reset());
+
+    harness.set_game_mode(2);
+    harness.tick();
+    assert!(harness.split());
+    assert!(!harness.reset());
+
+    harness.set_scene("BlackDragonBattleGameSceneController");
+    harness.set_boss("Bos111", 0);
+    harness.tick();
+    harness.set_boss("Bos111", 3);
+    harness.tick();
+    assert!(harness.split());
+
+    // The Black Dragon only splits when enabled, like outside of All Stories
+    harness.settings.black_dragon = false;
+    harness.settings.repeat_splits = true;
+    harness.set_boss("Bos111", 0);
+    harness.tick();
+    harness.set_boss("Bos111", 3);
+    harness.tick();
+    assert!(!harness.split());
+}
+
+#[test]
+fn all_stories_transition_splits_are_optional() {
+    let mut harness = Harness::new();
+    harness.settings.all_stories = true;
+    harness.settings.all_stories_trip = false;
+    harness.settings.all_stories_last_story = false;
+    harness.set_first_play(false, true);
+    harness.tick();
+
+    harness.set_first_play(false, false);
+    harness.tick();
+    assert!(!harness.split());
+
+    harness.set_game_mode(2);
+    harness.tick();
+    assert!(!harness.split());
+}
+
+/// Enters a Time Attack of the first act, up to the end of the countdown
+fn start_time_attack() -> Harness {
+    let mut harness = Harness::new();
+    harness.settings.time_attack = true;
+    harness.set_level(10100);
+    harness.set_time_attack(true);
+    harness.tick();
+    assert!(!harness.start());
+
+    harness.set_stage_time(0.5);
+    harness.tick();
+    harness
+}
+
+#[test]
+fn time_attack_starts_when_the_clock_starts() {
+    let harness = start_time_attack();
+    assert!(harness.start());
+    assert_eq!(harness.game_time(), Some(Duration::milliseconds(500)));
+}
+
+#[test]
+fn time_attack_splits_on_the_goal() {
+    let mut harness = start_time_attack();
+    harness.set_stage_time(45.0);
+    harness.tick();
+    assert!(!harness.split());
+
+    harness.set_goal(true, false);
+    harness.tick();
+    assert!(harness.split());
+    assert_eq!(harness.game_time(), Some(Duration::seconds(45)));
+
+    // The results don't split a second time
+    harness.set_goal(false, true);
+    harness.tick();
+    assert!(!harness.split());
+}
+
+#[test]
+fn time_attack_resets_on_retry() {
+    let mut harness = start_time_attack();
+    harness.set_stage_time(20.0);
+    harness.tick();
+    assert!(!harness.reset());
+
+    harness.set_stage_time(0.0);
+    harness.tick();
+    assert!(harness.reset());
+}
+
+#[test]
+fn time_attack_resets_when_quitting() {
+    let mut harness = start_time_attack();
+    harness.set_stage_time(20.0);
+    harness.tick();
+
+    harness.set_scene("TitleSceneController");
+    harness.tick();
+    assert!(harness.reset());
+}
+
+#[test]
+fn battle_starts_and_splits_on_each_round() {
+    let mut harness = Harness::new();
+    harness.settings.battle_mode = true;
+    harness.set_battle_round(1);
+    harness.set_scene("BRMainGameSceneController");
+    harness.tick();
+    assert!(harness.start());
+    assert!(!harness.split());
+
+    harness.set_battle_round(2);
+    harness.tick();
+    assert!(harness.split());
+    harness.tick();
+    assert!(!harness.split());
+
+    harness.set_scene("BROverallResultSceneController");
+    harness.tick();
+    assert!(harness.split());
+}
+
+#[test]
+fn battle_round_splits_are_optional() {
+    let mut harness = Harness::new();
+    harness.settings.battle_mode = true;
+    harness.settings.battle_round = false;
+    harness.set_battle_round(1);
+    harness.set_scene("BRMainGameSceneController");
+    harness.tick();
+
+    harness.set_battle_round(2);
+    harness.tick();
+    assert!(!harness.split());
+
+    harness.set_scene("BROverallResultSceneController");
+    harness.tick();
+    assert!(harness.split());
+}
+
+#[test]
+fn battle_rounds_dont_split_until_the_round_is_resolved() {
+    let mut harness = Harness::new();
+    harness.memory.battle_offsets.round =
+        LateField::new("BRMainGameSceneController", "currentRound");
+    harness.settings.battle_mode = true;
+    harness.set_battle_round(1);
+    harness.set_scene("BRMainGameSceneController");
+    harness.tick();
+    assert!(harness.start());
+
+    harness.set_battle_round(2);
+    harness.tick();
+    assert!(!harness.split());
+
+    // The overall results don't need the field
+    harness.set_scene("BROverallResultSceneController");
+    harness.tick();
+    assert!(harness.split());
+}
+
+#[test]
+fn game_modes_are_worked_out_from_the_scene() {
+    assert_eq!(GameMode::new(2, false, false), GameMode::LastStory);
+    assert_eq!(GameMode::new(7, false, false), GameMode::Unknown(7));
+    assert_eq!(GameMode::new(0, true, false), GameMode::TimeAttack);
+    assert_eq!(GameMode::new(7, false, true), GameMode::Battle);
+
+    let mut harness = Harness::new();
+    harness.set_scene("BRMainGameSceneController");
+    harness.tick();
+    assert_eq!(
+        harness.watchers.game_mode.pair.unwrap().current,
+        GameMode::Battle
+    );
+}
+
+#[test]
+fn acts_dont_split_outside_of_the_stories() {
+    let mut harness = Harness::new();
+    harness.set_game_mode(3);
+    harness.set_level(10100);
+    harness.tick();
+    assert!(!harness.clear_act());
+
+    harness.set_game_mode(42);
+    harness.set_level(10200);
+    harness.tick();
+    assert_eq!(
+        harness.watchers.game_mode.pair.unwrap().current,
+        GameMode::Unknown(42)
+    );
+    assert!(!harness.clear_act());
+}
+
+#[test]
+fn replays_story_opening() {
+    let actions = replay(
+        include_str!("../traces/story_opening.trace"),
+        &default_settings(),
+    );
+
+    assert_eq!(
+        actions,
+        [
+            (120, Action::Start),
+            (300, Action::Pause),
+            (420, Action::Resume),
+            (5400, Action::Split),
+            (5460, Action::Pause),
+            (5600, Action::Resume),
+            (9300, Action::Split),
+        ]
+    );
+}
+
+#[test]
+fn replays_last_story() {
+    let actions = replay(
+        include_str!("../traces/last_story.trace"),
+        &default_settings(),
+    );
+
+    assert_eq!(
+        actions,
+        [
+            (60, Action::Start),
+            (4000, Action::Split),
+            (4500, Action::Reset),
+            (4500, Action::Start),
+        ]
+    );
+}
+
+#[test]
+fn game_scenes_are_detected_through_inheritance() {
+    let mut harness = Harness::new();
+    harness.set_scene("SomeFutureGameSceneController");
+    harness.set_level(10100);
+    harness.tick();
+    assert_eq!(harness.watchers.level_id.pair.unwrap().current, 10100);
+
+    let class = harness
+        .watchers
+        .scene_controller_class
+        .pair
+        .unwrap()
+        .current;
+    assert!(derives_from(
+        &harness.game,
+        class,
+        "GameSceneControllerBase"
+    ));
+    assert!(!derives_from(&harness.game, class, "EnemySpecialBase"));
+    assert!(!derives_from(
+        &harness.game,
+        Address::NULL,
+        "GameSceneControllerBase"
+    ));
+}
@@ -0,0 +1,55 @@
+//! The actions the autosplitter can take on the timer. They're kept behind a trait, so that
+//! replaying a trace goes through the exact same logic as a live run.
+
+use asr::{
+    time::Duration,
+    timer::{self, TimerState},
+};
+
+pub trait TimerControl {
+    fn state(&self) -> TimerState;
+    fn start(&mut self);
+    fn split(&mut self);
+    fn skip_split(&mut self);
+    fn reset(&mut self);
+    fn pause_game_time(&mut self);
+    fn resume_game_time(&mut self);
+    fn set_game_time(&mut self, time: Duration);
+}
+
+/// The timer of LiveSplit itself
+pub struct LiveSplit;
+
+impl TimerControl for LiveSplit {
+    fn state(&self) -> TimerState {
+        timer::state()
+    }
+
+    fn start(&mut self) {
+        timer::start()
+    }
+
+    fn split(&mut self) {
+        timer::split()
+    }
+
+    fn skip_split(&mut self) {
+        timer::skip_split()
+    }
+
+    fn reset(&mut self) {
+        timer::reset()
+    }
+
+    fn pause_game_time(&mut self) {
+        timer::pause_game_time()
+    }
+
+    fn resume_game_time(&mut self) {
+        timer::resume_game_time()
+    }
+
+    fn set_game_time(&mut self, time: Duration) {
+        timer::set_game_time(time)
+    }
+}
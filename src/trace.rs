@@ -0,0 +1,283 @@
+//! Recording of the watched values, so full runs can be replayed on the host as regression tests.
+//!
+//! Each line of a trace is a single change, in the form `<tick> <field> <value>`. Booleans are
+//! stored as `0` / `1`, durations in milliseconds and addresses as plain numbers. When recording,
+//! every line is prefixed with [`PREFIX`] so the trace can be easily extracted from the LiveSplit
+//! log.
+//!
+//! Every value the autosplitting logic relies on is recorded, while everything that follows from
+//! them is worked out again when replaying.
+
+use asr::{time::Duration, watcher::Watcher, Address};
+
+use crate::Watchers;
+
+pub const PREFIX: &str = "trace: ";
+
+/// A watched value, as stored in a trace
+trait TraceValue: Copy + PartialEq {
+    fn to_trace(self) -> u64;
+    #[cfg(test)]
+    fn from_trace(val: u64) -> Self;
+}
+
+impl TraceValue for bool {
+    fn to_trace(self) -> u64 {
+        self as u64
+    }
+
+    #[cfg(test)]
+    fn from_trace(val: u64) -> Self {
+        val != 0
+    }
+}
+
+impl TraceValue for u32 {
+    fn to_trace(self) -> u64 {
+        self as u64
+    }
+
+    #[cfg(test)]
+    fn from_trace(val: u64) -> Self {
+        val as u32
+    }
+}
+
+impl TraceValue for Duration {
+    fn to_trace(self) -> u64 {
+        self.whole_milliseconds() as u64
+    }
+
+    #[cfg(test)]
+    fn from_trace(val: u64) -> Self {
+        Duration::milliseconds(val as i64)
+    }
+}
+
+impl TraceValue for Address {
+    fn to_trace(self) -> u64 {
+        self.value()
+    }
+
+    #[cfg(test)]
+    fn from_trace(val: u64) -> Self {
+        Address::new(val)
+    }
+}
+
+/// Lists the recorded watchers once, for both recording and replaying them. The current save
+/// slot isn't part of it, as changing it starts some of the others over, so it's always handled
+/// first.
+macro_rules! fields {
+    ($($field:ident),* $(,)?) => {
+        #[cfg(test)]
+        const FIELDS: &[&str] = &[$(stringify!($field)),*];
+
+        fn record_fields(tick: u64, watchers: &Watchers, full: bool) {
+            $(record_field(tick, stringify!($field), &watchers.$field, full);)*
+        }
+
+        /// Updates the watchers with the given values, in the order of [`FIELDS`]. Values we
+        /// haven't seen yet are left alone, exactly as if they couldn't be read yet.
+        #[cfg(test)]
+        fn apply_fields(watchers: &mut Watchers, values: &[Option<u64>]) {
+            let mut values = values.iter().copied();
+            $(
+                if let Some(val) = values.next().flatten() {
+                    watchers.$field.update_infallible(TraceValue::from_trace(val));
+                }
+            )*
+        }
+    };
+}
+
+fields!(
+    is_game_scene,
+    scene_controller_class,
+    is_title_screen,
+    is_world_map,
+    is_special_stage,
+    is_battle,
+    is_battle_result,
+    battle_round,
+    start_trigger,
+    start_trigger_trip,
+    emeralds,
+    level_id,
+    is_time_attack,
+    time_attack_goal,
+    goal_sequence,
+    result_sequence,
+    goal_ring_flag,
+    is_loading,
+    stage_time,
+    raw_game_mode,
+    boss_class,
+    boss_defeated,
+);
+
+/// Logs the watched values that changed during the current tick. If `full` is set, every value
+/// is logged instead, which is needed for the first tick of a recording.
+pub fn record(tick: u64, watchers: &Watchers, full: bool) {
+    record_field(tick, "current_slot", &watchers.current_slot, full);
+
+    // The watchers of the save have just started over, so their values might not count as changes
+    let full = full || watchers.current_slot.pair.is_some_and(|val| val.changed());
+    record_fields(tick, watchers, full);
+}
+
+fn record_field<T: TraceValue>(tick: u64, name: &str, watcher: &Watcher<T>, full: bool) {
+    if let Some(pair) = watcher.pair.filter(|val| full || val.changed()) {
+        crate::log(&format_args!(
+            "{PREFIX}{tick} {name} {}",
+            pair.current.to_trace()
+        ));
+    }
+}
+
+#[cfg(test)]
+pub mod replay {
+    use asr::{time::Duration, timer::TimerState};
+
+    use super::{apply_fields, TraceValue, FIELDS, PREFIX};
+    use crate::{timer_control::TimerControl, Settings, Watchers};
+
+    /// What the autosplitter asked the timer to do, as seen while replaying a trace
+    #[derive(Debug, Copy, Clone, PartialEq, Eq)]
+    pub enum Action {
+        Start,
+        Split,
+        SkipSplit,
+        Reset,
+        Pause,
+        Resume,
+    }
+
+    /// Stands in for the timer, keeping track of what it's been asked to do
+    struct ReplayTimer {
+        tick: u64,
+        state: TimerState,
+        is_paused: bool,
+        /// Whether the game time was paused at the end of the previous tick
+        was_paused: bool,
+        actions: Vec<(u64, Action)>,
+    }
+
+    impl ReplayTimer {
+        /// Game time gets paused and resumed several times within a single tick, so only the
+        /// state it ends the tick in counts
+        fn end_tick(&mut self) {
+            if self.state != TimerState::NotRunning && self.is_paused != self.was_paused {
+                let action = if self.is_paused {
+                    Action::Pause
+                } else {
+                    Action::Resume
+                };
+                self.actions.push((self.tick, action));
+            }
+            self.was_paused = self.state != TimerState::NotRunning && self.is_paused;
+        }
+    }
+
+    impl TimerControl for ReplayTimer {
+        fn state(&self) -> TimerState {
+            self.state
+        }
+
+        fn start(&mut self) {
+            self.state = TimerState::Running;
+            self.actions.push((self.tick, Action::Start));
+        }
+
+        fn split(&mut self) {
+            self.actions.push((self.tick, Action::Split));
+        }
+
+        fn skip_split(&mut self) {
+            self.actions.push((self.tick, Action::SkipSplit));
+        }
+
+        fn reset(&mut self) {
+            self.state = TimerState::NotRunning;
+            self.actions.push((self.tick, Action::Reset));
+        }
+
+        fn pause_game_time(&mut self) {
+            self.is_paused = true;
+        }
+
+        fn resume_game_time(&mut self) {
+            self.is_paused = false;
+        }
+
+        fn set_game_time(&mut self, _time: Duration) {}
+    }
+
+    /// Feeds a recorded trace through the autosplitting logic and returns every timer action it
+    /// caused, alongside the tick it happened on.
+    pub fn replay(trace: &str, settings: &Settings) -> Vec<(u64, Action)> {
+        let mut changes = trace
+            .lines()
+            .map(|line| {
+                line.split_once(PREFIX)
+                    .map_or(line, |(_, line)| line)
+                    .trim()
+            })
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .map(|line| {
+                let mut parts = line.split_whitespace();
+                let mut next = || {
+                    parts
+                        .next()
+                        .unwrap_or_else(|| panic!("Malformed line: {line}"))
+                };
+                let tick = next().parse::<u64>().expect("Invalid tick");
+                let field = next();
+                let value = next().parse::<u64>().expect("Invalid value");
+                (tick, field, value)
+            })
+            .peekable();
+
+        let mut watchers = Watchers::default();
+        let mut current_slot = None;
+        let mut values = vec![None::<u64>; FIELDS.len()];
+        let mut timer = ReplayTimer {
+            tick: 0,
+            state: TimerState::NotRunning,
+            is_paused: false,
+            was_paused: false,
+            actions: Vec::new(),
+        };
+
+        while let Some(&(tick, _, _)) = changes.peek() {
+            while let Some((_, field, value)) = changes.next_if(|&(t, _, _)| t == tick) {
+                if field == "current_slot" {
+                    current_slot = Some(value);
+                    continue;
+                }
+
+                let index = FIELDS
+                    .iter()
+                    .position(|&val| val == field)
+                    .unwrap_or_else(|| panic!("Unknown field: {field}"));
+                values[index] = Some(value);
+            }
+
+            // Same order of execution as the main loop
+            if let Some(val) = current_slot {
+                watchers
+                    .current_slot
+                    .update_infallible(TraceValue::from_trace(val));
+            }
+            crate::restart_save_watchers(&mut watchers);
+            apply_fields(&mut watchers, &values);
+            crate::update_state(&mut watchers, settings);
+
+            timer.tick = tick;
+            crate::update_timer(&mut timer, &mut watchers, settings);
+            timer.end_tick();
+        }
+
+        timer.actions
+    }
+}
@@ -0,0 +1,11 @@
+use asr::Process;
+
+/// Logs the size of `GameAssembly.dll`, which changes with every build of the game, so a bug
+/// report can be traced back to the build it comes from. The module has to be loaded already,
+/// which is the case once the IL2CPP module has been found.
+pub fn log_build(game: &Process) {
+    match game.get_module_size("GameAssembly.dll") {
+        Ok(size) => crate::log(&format_args!("  => GameAssembly.dll size: {size:#X}")),
+        Err(_) => crate::log(&"  => Could not read the size of GameAssembly.dll"),
+    }
+}